@@ -9,27 +9,30 @@
 
 // Internal modules
 use crate::cache::list_mods_cached;
-use crate::consts::USER_AGENT;
-use crate::downloader::download_multiple_mods;
+use crate::consts::DEFAULT_JOBS;
+use crate::downloader::download_mods_reported;
+use crate::error::MineFetchError;
+use crate::http::{get_json, post_json};
+use crate::jar::read_metadata;
+use crate::metacache::get_cached_json;
 use crate::mfio::select;
-use crate::profile::{get_locks, remove_locked_ones, write_lock};
+use crate::profile::{get_locks, write_lock};
 use crate::structs::{
-    Dependency, File, Hash, Hit, MFHashMap, ProjectList, Search, VersionsList, WorkingProfile,
+    Dependency, File, Hash, Hit, MFHashMap, Member, ProjectInfo, ProjectList, Search, SearchQuery,
+    Version, VersionsList, WorkingProfile,
 };
 use crate::utils::{get_hashes, remove_mods_by_hash};
 
 // External crates
-use async_recursion::async_recursion;
-use reqwest::Client;
+use futures::stream::{self, StreamExt};
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
-use serde_json::from_str;
 use serde_json::json;
 
 // Standard libraries
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::error::Error;
+use std::path::Path;
 use std::sync::Arc;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -41,23 +44,57 @@ pub struct Anymod {
     pub filename: String,
     pub hash: String,
     pub url: String,
+    #[serde(default)]
+    pub size: Option<u64>,
     pub depends: Option<Vec<Dependency>>,
 }
 
-pub fn get_primary(files: &Vec<File>) -> Result<File, Box<dyn Error>> {
+impl Anymod {
+    /// Creates an entry for a jar that isn't known to Modrinth (yet)
+    pub fn unresolved(path: &Path, hash: String) -> Self {
+        let metadata = read_metadata(path);
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Anymod {
+            title: metadata
+                .as_ref()
+                .map(|metadata| metadata.name.clone().unwrap_or(metadata.id.clone())),
+            project_id: String::new(),
+            version_name: metadata
+                .and_then(|metadata| metadata.version)
+                .unwrap_or_default(),
+            version_id: String::new(),
+            filename,
+            hash,
+            url: String::new(),
+            size: std::fs::metadata(path).ok().map(|metadata| metadata.len()),
+            depends: None,
+        }
+    }
+
+    /// False for jars that haven't been matched with a Modrinth project
+    pub fn is_resolved(&self) -> bool {
+        !self.project_id.is_empty()
+    }
+}
+
+pub fn get_primary(files: &[File]) -> Result<File, MineFetchError> {
     let file = files
         .iter()
         .find(|file| file.primary)
-        .ok_or("Couldn't get the primary file")?;
+        .ok_or_else(|| MineFetchError::NotFound("Couldn't get the primary file".into()))?;
 
     Ok(file.clone())
 }
 
-/// Gets the latest version of the mod by slug or id
-pub async fn get_latest_version(
-    modname: &String,
+/// Fetches versions of the mod that are compatible with the profile (newest first)
+pub async fn fetch_versions(
+    modname: &str,
     working_profile: &WorkingProfile,
-) -> Result<Anymod, Box<dyn std::error::Error>> {
+) -> Result<VersionsList, MineFetchError> {
     // Set the parameters for the URL
     let params = &[
         (
@@ -76,191 +113,236 @@ pub async fn get_latest_version(
         params,
     )?;
 
-    // Send the request.
-    let response = working_profile
-        .client
-        .get(url)
-        .header("User-Agent", USER_AGENT)
-        .send()
+    // Send the request and parse the response
+    match get_cached_json(working_profile, url).await {
+        Err(MineFetchError::Status { status: 404, .. }) => Err(MineFetchError::NotFound(format!(
+            "Cannot find such mod: {modname}"
+        ))),
+        result => result,
+    }
+}
+
+/// Fetches the latest compatible version of the mod by slug or id
+pub async fn fetch_latest_version(
+    modname: &str,
+    working_profile: &WorkingProfile,
+) -> Result<Version, MineFetchError> {
+    // Get the first version.
+    fetch_versions(modname, working_profile)
         .await?
-        .text()
-        .await?;
+        .into_iter()
+        .next()
+        .ok_or_else(|| MineFetchError::NotFound(format!("No versions available for {modname}")))
+}
 
-    // Parse the response.
-    let parsed: VersionsList = from_str(&response).map_err(|_| "Cannot find such mod")?;
+/// Turns a version into Anymod, fails if its file is locked
+fn locked_or_anymod(
+    modname: &str,
+    version: Version,
+    title: Option<String>,
+    locks: &[String],
+) -> Result<Anymod, MineFetchError> {
+    let file = get_primary(&version.files)?;
 
-    // Get the first version.
-    let version = parsed.get(0).ok_or("No versions available")?;
+    // Check if this mod is in locks or not
+    if locks.contains(&file.hashes.sha1) {
+        return Err(MineFetchError::Locked(title.unwrap_or(modname.to_string())));
+    }
+
+    version_to_anymod(version, title)
+}
+
+/// Turns a version into Anymod using its primary file
+pub fn version_to_anymod(
+    version: Version,
+    title: Option<String>,
+) -> Result<Anymod, MineFetchError> {
+    let file = get_primary(&version.files)?;
+
+    Ok(Anymod {
+        title,
+        project_id: version.project_id,
+        version_name: version.name,
+        version_id: version.id,
+        filename: file.filename,
+        hash: file.hashes.sha1,
+        url: file.url,
+        size: Some(file.size),
+        depends: version.dependencies,
+    })
+}
+
+/// Gets the latest version of the mod by slug or id
+pub async fn get_latest_version(
+    modname: &str,
+    working_profile: &WorkingProfile,
+) -> Result<Anymod, MineFetchError> {
+    let version = fetch_latest_version(modname, working_profile).await?;
 
     // Search for locks
     let locks = get_locks(&working_profile.profile)
         .await
         .unwrap_or_default();
 
-    let file = get_primary(&version.files)?;
-
-    // Check if this mod is in locks or not
-    for lock in locks {
-        if file.hashes.sha1 == lock {
-            return Err("This mod is locked".into());
-        }
-    }
-
-    let title = get_projects_name(&working_profile.client, vec![&version.project_id])
+    let title = get_projects_name(working_profile, vec![&version.project_id])
         .await?
         .first()
-        .ok_or("The project list is empty")?
+        .ok_or_else(|| MineFetchError::NotFound("The project list is empty".into()))?
         .title
         .clone();
 
-    let anymod = Anymod {
-        title: Some(title.clone()),
-        project_id: version.project_id.clone(),
-        version_name: version.name.clone(),
-        version_id: version.id.clone(),
-        filename: file.filename.clone(),
-        hash: file.hashes.sha1.clone(),
-        url: file.url.clone(),
-        depends: version.dependencies.clone(),
-    };
-
-    Ok(anymod)
+    locked_or_anymod(modname, version, Some(title), &locks)
 }
 
 /// Mod search
 pub async fn search_mods(
-    query: &str,
+    search: &SearchQuery,
     working_profile: &WorkingProfile,
-) -> Result<Vec<Hit>, Box<dyn Error>> {
-    // Set facets
-    let facets = json!([
-        [format!("categories:{}", working_profile.profile.loader)],
-        [format!("versions:{}", working_profile.profile.gameversion)],
-        ["project_type:mod"],
-    ]);
+) -> Result<Vec<Hit>, MineFetchError> {
+    // Set facets (every inner list is joined with AND)
+    let mut facets = vec![
+        vec![format!("categories:{}", working_profile.profile.loader)],
+        vec![format!("versions:{}", working_profile.profile.gameversion)],
+        vec!["project_type:mod".to_string()],
+    ];
+
+    for category in &search.categories {
+        facets.push(vec![format!("categories:{category}")]);
+    }
+
+    if let Some(author) = &search.author {
+        facets.push(vec![format!("author:{author}")]);
+    }
 
     // Set parameters
-    let params: &[(&str, &str)] = &[("query", &query), ("facets", &facets.to_string())];
+    let params: &[(&str, String)] = &[
+        ("query", search.query.clone()),
+        ("facets", json!(facets).to_string()),
+        ("index", search.sort.clone()),
+        ("limit", search.limit.to_string()),
+        ("offset", ((search.page - 1) * search.limit).to_string()),
+    ];
 
     // Parse the URL
     let url = Url::parse_with_params("https://api.modrinth.com/v2/search", params)?;
 
-    // Send the request
-    let response = working_profile
-        .client
-        .get(url)
-        .header("User-Agent", USER_AGENT)
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    // Parse the response
-    let parsed: Search = from_str(&response)?;
+    // Send the request and parse the response
+    let parsed: Search = get_json(&working_profile.client, url).await?;
 
     // Check if there's no hits
     if parsed.hits.is_empty() {
-        return Err("No hits".into());
+        return Err(MineFetchError::NotFound(format!(
+            "No hits for '{}'",
+            search.query
+        )));
     }
 
     Ok(parsed.hits)
 }
 
-/// Updates mods to the latest version
-pub async fn upgrade_mods(
+/// An update that Modrinth has for an installed mod
+pub struct PendingUpdate {
+    /// Hash of the installed jar
+    pub old_hash: String,
+
+    /// The new version's primary file
+    pub new_mod: Anymod,
+
+    /// Release channel of the new version (release, beta, alpha)
+    pub channel: String,
+
+    /// The installed jar is locked, so 'update' will skip it
+    pub locked: bool,
+}
+
+/// Finds updates for every installed mod, locked ones included.
+/// Also returns hashes of jars that have no version for the profile's loader and game version
+pub async fn find_updates(
     working_profile: &WorkingProfile,
-) -> Result<(Vec<String>, Vec<Anymod>), Box<dyn Error>> {
+) -> Result<(Vec<PendingUpdate>, Vec<String>), MineFetchError> {
     // Get hashes from mods' directory
-    let hashes = get_hashes(&working_profile.profile.modsfolder).await?;
+    let installed = get_hashes(&working_profile.profile.modsfolder).await?;
 
     // Create a Hash structure to send to the API server
     let hashes = Hash {
-        hashes,
+        hashes: installed.clone(),
         algorithm: "sha1".to_string(),
         loaders: Some(vec![working_profile.profile.loader.to_string()]),
         game_versions: Some(vec![working_profile.profile.gameversion.to_string()]),
     };
 
-    // Transform into json string
-    let hashes_send = serde_json::to_string(&hashes)?;
-
-    // Send a request
-    let response = working_profile
-        .client
-        .post("https://api.modrinth.com/v2/version_files/update")
-        .header("User-Agent", USER_AGENT)
-        .header("Content-Type", "application/json")
-        .body(hashes_send)
-        .send()
-        .await
-        .map_err(|error| error)?
-        .text()
-        .await?;
-
     /*
-        Parse the response.
+        Send a request and parse the response.
         This is a list of mods which
         includes both updated mods and
         those that have not been changed.
     */
 
-    let mut versions: MFHashMap = serde_json::from_str(&response)?;
+    let versions: MFHashMap = post_json(
+        &working_profile.client,
+        "https://api.modrinth.com/v2/version_files/update",
+        &hashes,
+    )
+    .await?;
 
     // Search for locks
-    let locks: Vec<String> = match get_locks(&working_profile.profile).await {
-        Ok(locks) => locks,
-        Err(_) => Vec::new(),
-    };
+    let locks: Vec<String> = get_locks(&working_profile.profile)
+        .await
+        .unwrap_or_default();
 
-    /*
-        If locks are empty then do nothing.
-        If there're locks then remove them from 'versions' and then return modified version list
-    */
+    // Modrinth leaves out the jars it has nothing for
+    let unsupported: Vec<String> = installed
+        .into_iter()
+        .filter(|hash| !versions.contains_key(hash))
+        .collect();
 
-    let versions = if locks.is_empty() {
-        &mut versions
-    } else {
-        remove_locked_ones(&mut versions, locks).await?
-    };
+    let mut updates: Vec<PendingUpdate> = Vec::new();
 
-    /*
-        Get the hashes (keys in HashMap) that must be removed from version HashMap.
-        It's needed to filter out the mods that weren't updated.
-    */
+    for (hash, version) in versions {
+        let files = get_primary(&version.files)?;
 
-    let keys_to_remove: Vec<String> = versions
-        .iter()
-        .map(|(_, b)| get_primary(&b.files).unwrap().hashes.sha1)
-        .collect();
+        // The latest version is the installed one, nothing to do
+        if files.hashes.sha1 == hash {
+            continue;
+        }
 
-    // Remove the hashes that were found above
-    for key in &keys_to_remove {
-        versions.remove(key);
-    }
+        let anymod = Anymod {
+            title: None,
+            project_id: version.project_id.clone(),
+            version_name: version.name.clone(),
+            version_id: version.id.clone(),
+            filename: files.filename.clone(),
+            hash: files.hashes.sha1.clone(),
+            url: files.url.clone(),
+            size: Some(files.size),
+            depends: version.dependencies.clone(),
+        };
 
-    let mut new_versions: Vec<Anymod> = Vec::new();
+        updates.push(PendingUpdate {
+            locked: locks.contains(&hash),
+            old_hash: hash,
+            new_mod: anymod,
+            channel: version.version_type,
+        });
+    }
 
-    let mut old_versions: Vec<String> = Vec::new();
+    // Return the lists (they can be empty)
+    Ok((updates, unsupported))
+}
 
-    // Fill the 'new_versions' and 'old_versions' lists
-    for (hash, version) in versions {
-        if let Some(files) = Some(get_primary(&version.files)?) {
-            let anymod = Anymod {
-                title: None,
-                project_id: version.project_id.clone(),
-                version_name: version.name.clone(),
-                version_id: version.id.clone(),
-                filename: files.filename.clone(),
-                hash: files.hashes.sha1.clone(),
-                url: files.url.clone(),
-                depends: version.dependencies.clone(),
-            };
-            new_versions.push(anymod);
-            old_versions.push(hash.clone());
-        }
-    }
+/// Updates mods to the latest version
+pub async fn upgrade_mods(
+    working_profile: &WorkingProfile,
+) -> Result<(Vec<String>, Vec<Anymod>), MineFetchError> {
+    // Locked mods stay as they are
+    let (old_versions, new_versions) = find_updates(working_profile)
+        .await?
+        .0
+        .into_iter()
+        .filter(|update| !update.locked)
+        .map(|update| (update.old_hash, update.new_mod))
+        .unzip();
 
-    // Return the list (it can be empty)
     Ok((old_versions, new_versions))
 }
 
@@ -268,58 +350,19 @@ pub async fn upgrade_mods(
 pub async fn get_mods_from_hash(
     working_profile: &WorkingProfile,
     hashes: Hash,
-) -> Result<Vec<Anymod>, Box<dyn std::error::Error>> {
-    // Parse into json string
-    let hashes_send = serde_json::to_string(&hashes)?;
-
+) -> Result<Vec<Anymod>, MineFetchError> {
     // Define the URL
     let url = "https://api.modrinth.com/v2/version_files";
 
-    // Send the post request with json string
-    let response = working_profile
-        .client
-        .post(url)
-        .header("User-Agent", USER_AGENT)
-        .header("Content-Type", "application/json")
-        .body(hashes_send)
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    // Parse the response
-    let versions: MFHashMap = serde_json::from_str(&response)?;
-
-    let pairs: Vec<(
-        String,
-        String,
-        String,
-        String,
-        String,
-        String,
-        Option<Vec<Dependency>>,
-    )> = versions
-        .iter()
-        .map(|(_, v)| {
-            let name = v.name.clone();
-            let id = v.project_id.clone();
-            let file = v.files.iter().find(|f| f.primary).expect("no primary file");
-
-            (
-                name,
-                id,
-                file.filename.clone(),
-                v.id.clone(),
-                file.hashes.sha1.clone(),
-                file.url.clone(),
-                v.dependencies.clone(),
-            )
-        })
-        .collect();
+    // Send the post request with json string and parse the response
+    let versions: MFHashMap = post_json(&working_profile.client, url, &hashes).await?;
 
-    let project_ids: Vec<&String> = pairs.iter().map(|(_, id, _, _, _, _, _)| id).collect();
+    let project_ids: Vec<&String> = versions
+        .values()
+        .map(|version| &version.project_id)
+        .collect();
 
-    let projects = get_projects_name(&working_profile.client, project_ids).await?;
+    let projects = get_projects_name(working_profile, project_ids).await?;
 
     let projects_map: HashMap<String, String> = projects
         .into_iter()
@@ -328,16 +371,18 @@ pub async fn get_mods_from_hash(
 
     let mut end: Vec<Anymod> = Vec::new();
 
-    for (name, pid, filename, vid, hash, url, depends) in pairs.iter() {
+    for version in versions.values() {
+        let file = get_primary(&version.files)?;
         let anymod = Anymod {
-            title: Some(projects_map[pid].clone()),
-            version_name: name.clone(),
-            version_id: vid.clone(),
-            project_id: pid.clone(),
-            filename: filename.clone(),
-            hash: hash.clone(),
-            url: url.clone(),
-            depends: depends.clone(),
+            title: projects_map.get(&version.project_id).cloned(),
+            version_name: version.name.clone(),
+            version_id: version.id.clone(),
+            project_id: version.project_id.clone(),
+            filename: file.filename,
+            hash: file.hashes.sha1,
+            url: file.url,
+            size: Some(file.size),
+            depends: version.dependencies.clone(),
         };
         end.push(anymod);
     }
@@ -348,10 +393,15 @@ pub async fn get_mods_from_hash(
     Ok(end)
 }
 
+/// Gets titles and ids of the projects (cached)
 pub async fn get_projects_name(
-    client: &Client,
-    project_id: Vec<&String>,
-) -> Result<ProjectList, Box<dyn Error>> {
+    working_profile: &WorkingProfile,
+    mut project_id: Vec<&String>,
+) -> Result<ProjectList, MineFetchError> {
+    // The same set of IDs must give the same URL for the cache
+    project_id.sort();
+    project_id.dedup();
+
     // Join all IDs into a comma-separated string
     let ids = json!(project_id);
 
@@ -359,55 +409,156 @@ pub async fn get_projects_name(
     let params = &[("ids", ids.to_string())];
 
     let url = reqwest::Url::parse_with_params("https://api.modrinth.com/v2/projects", params)?;
-    let response = client
-        .get(url)
-        .header("User-Agent", USER_AGENT)
-        .send()
-        .await?
-        .text()
-        .await?;
 
-    let parsed: ProjectList = serde_json::from_str(&response)?;
+    let parsed: ProjectList = get_cached_json(working_profile, url).await?;
 
     Ok(parsed)
 }
 
-#[async_recursion]
-pub async fn resolve_dependencies_recursive(
-    dependencies: &[Dependency],
+/// Gets versions newer than the installed one up to the new one (newest first)
+pub async fn get_changelogs(
+    installed_version_id: &str,
+    new_mod: &Anymod,
     working_profile: &WorkingProfile,
-    processed: &mut HashSet<String>,
-    result: &mut Vec<(Anymod, String)>,
-) -> Result<(), Box<dyn Error>> {
-    for dependency in dependencies {
-        // Skip already processed mods
-        if !processed.insert(dependency.project_id.clone()) {
-            continue;
-        }
+) -> Result<VersionsList, MineFetchError> {
+    let versions = fetch_versions(&new_mod.project_id, working_profile).await?;
 
-        let anymod = get_latest_version(&dependency.project_id, working_profile).await?;
+    // If the installed version isn't in the list, show only the new one
+    let known = versions
+        .iter()
+        .any(|version| version.id == installed_version_id);
 
-        let dep_type = dependency.dependency_type.clone();
-        result.push((anymod.clone(), dep_type));
+    Ok(versions
+        .into_iter()
+        .skip_while(|version| version.id != new_mod.version_id)
+        .take_while(|version| version.id != installed_version_id)
+        .take(if known { usize::MAX } else { 1 })
+        .collect())
+}
 
-        // Get dependencies for this dependency, recursively
-        if let Some(subdeps) = &anymod.depends {
-            resolve_dependencies_recursive(subdeps, working_profile, processed, result).await?;
-        }
+/// Gets full project info by slug or id (cached)
+pub async fn get_project(
+    modname: &str,
+    working_profile: &WorkingProfile,
+) -> Result<ProjectInfo, MineFetchError> {
+    let url = Url::parse(&format!("https://api.modrinth.com/v2/project/{modname}"))?;
+
+    match get_cached_json(working_profile, url).await {
+        Err(MineFetchError::Status { status: 404, .. }) => Err(MineFetchError::NotFound(format!(
+            "Cannot find such mod: {modname}"
+        ))),
+        result => result,
     }
+}
 
-    Ok(())
+/// Gets the team members of the project (cached)
+pub async fn get_members(
+    project_id: &str,
+    working_profile: &WorkingProfile,
+) -> Result<Vec<Member>, MineFetchError> {
+    let url = Url::parse(&format!(
+        "https://api.modrinth.com/v2/project/{project_id}/members"
+    ))?;
+
+    get_cached_json(working_profile, url).await
 }
 
+/// Resolves all dependencies level by level.
+/// Every level is fetched concurrently and the titles
+/// of its projects are fetched with a single request
+/// Installed dependencies aren't resolved again (a locked one can't be),
+/// they're returned as they are and their cached dependencies are followed
 pub async fn get_dependencies_recursive(
     dependencies: &[Dependency],
+    installed: &[Anymod],
     working_profile: &WorkingProfile,
-) -> Result<Vec<(Anymod, String)>, Box<dyn std::error::Error>> {
-    let mut processed = HashSet::new();
-    let mut result = Vec::new();
+) -> Result<Vec<(Anymod, String)>, MineFetchError> {
+    let mut processed: HashSet<String> = HashSet::new();
+    let mut result: Vec<(Anymod, String)> = Vec::new();
+
+    // Locks are read once for the whole tree
+    let locks = get_locks(&working_profile.profile)
+        .await
+        .unwrap_or_default();
+
+    let jobs = working_profile.settings.jobs.unwrap_or(DEFAULT_JOBS).max(1);
+
+    // Skip already processed mods
+    let mut level: Vec<Dependency> = dependencies
+        .iter()
+        .filter(|dependency| processed.insert(dependency.project_id.clone()))
+        .cloned()
+        .collect();
+
+    while !level.is_empty() {
+        let mut next_level: Vec<Dependency> = Vec::new();
+        let mut missing: Vec<Dependency> = Vec::new();
+
+        for dependency in std::mem::take(&mut level) {
+            let Some(anymod) = installed
+                .iter()
+                .find(|anymod| anymod.project_id == dependency.project_id)
+            else {
+                missing.push(dependency);
+                continue;
+            };
+
+            // Installed ones stay as they are
+            if let Some(subdeps) = &anymod.depends {
+                next_level.extend(
+                    subdeps
+                        .iter()
+                        .filter(|subdep| processed.insert(subdep.project_id.clone()))
+                        .cloned(),
+                );
+            }
+
+            result.push((anymod.clone(), dependency.dependency_type));
+        }
+
+        if missing.is_empty() {
+            level = next_level;
+            continue;
+        }
 
-    resolve_dependencies_recursive(dependencies, working_profile, &mut processed, &mut result)
-        .await?;
+        // Fetch the latest versions of this level at the same time
+        let versions: Vec<Version> = stream::iter(&missing)
+            .map(|dependency| fetch_latest_version(&dependency.project_id, working_profile))
+            .buffered(jobs)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        // Get all titles with one request
+        let titles: HashMap<String, String> = get_projects_name(
+            working_profile,
+            versions.iter().map(|version| &version.project_id).collect(),
+        )
+        .await?
+        .into_iter()
+        .map(|project| (project.id, project.title))
+        .collect();
+
+        for (dependency, version) in missing.iter().zip(versions) {
+            let title = titles.get(&version.project_id).cloned();
+            let anymod = locked_or_anymod(&dependency.project_id, version, title, &locks)?;
+
+            // Dependencies of this dependency go to the next level
+            if let Some(subdeps) = &anymod.depends {
+                next_level.extend(
+                    subdeps
+                        .iter()
+                        .filter(|subdep| processed.insert(subdep.project_id.clone()))
+                        .cloned(),
+                );
+            }
+
+            result.push((anymod, dependency.dependency_type.clone()));
+        }
+
+        level = next_level;
+    }
 
     Ok(result)
 }
@@ -416,37 +567,24 @@ pub async fn get_dependencies_recursive(
 pub async fn list_versions(
     working_profile: &WorkingProfile,
     project: String,
-    params: &[(&str, &String)],
-) -> Result<Vec<Anymod>, Box<dyn std::error::Error>> {
-    let client = working_profile.client.clone();
-    let url = reqwest::Url::parse_with_params(
-        &format!("https://api.modrinth.com/v2/project/{}/version", &project),
-        params,
-    )?;
+) -> Result<Vec<Anymod>, MineFetchError> {
+    let versions = fetch_versions(&project, working_profile).await?;
 
-    let response = client
-        .get(url)
-        .header("User-Agent", USER_AGENT)
-        .send()
-        .await
-        .unwrap()
-        .text()
-        .await
-        .unwrap();
-
-    let versions: VersionsList = serde_json::from_str(&response)?;
+    let project_id = versions
+        .first()
+        .ok_or_else(|| MineFetchError::NotFound(format!("No versions available for {project}")))?
+        .project_id
+        .clone();
 
-    let title = get_projects_name(&client, vec![&versions.first().unwrap().project_id])
+    let title = get_projects_name(working_profile, vec![&project_id])
         .await?
         .first()
-        .unwrap()
+        .ok_or_else(|| MineFetchError::NotFound("The project list is empty".into()))?
         .title
         .clone();
 
     let mut end: Vec<Anymod> = Vec::new();
 
-    let project_id = versions.first().unwrap().project_id.clone();
-
     for version in versions {
         let file = get_primary(&version.files)?;
         let anymod = Anymod {
@@ -457,6 +595,7 @@ pub async fn list_versions(
             filename: file.filename.clone(),
             hash: file.hashes.sha1.clone(),
             url: file.url.clone(),
+            size: Some(file.size),
             depends: version.dependencies,
         };
         end.push(anymod);
@@ -466,7 +605,7 @@ pub async fn list_versions(
 }
 
 /// Edits a mod
-pub async fn edit_mod(working_profile: &WorkingProfile) -> Result<(), Box<dyn Error>> {
+pub async fn edit_mod(working_profile: &WorkingProfile) -> Result<(), MineFetchError> {
     // Get the current mod list
     let modlist = list_mods_cached(working_profile).await?;
 
@@ -476,13 +615,13 @@ pub async fn edit_mod(working_profile: &WorkingProfile) -> Result<(), Box<dyn Er
     */
     let mut menu: Vec<(String, Anymod)> = Vec::new();
 
-    // Push mods into the 'menu' list
-    for modinfo in modlist {
+    // Push mods into the 'menu' list (unknown jars can't be edited)
+    for modinfo in modlist.into_iter().filter(Anymod::is_resolved) {
         menu.push((
             format!(
                 "{} ({})",
-                modinfo.title.as_ref().unwrap(), // Version name
-                modinfo.filename                 // Version filename
+                modinfo.title.clone().unwrap_or_default(), // Version name
+                modinfo.filename                           // Version filename
             ),
             modinfo, // What the program sees
         ));
@@ -491,20 +630,8 @@ pub async fn edit_mod(working_profile: &WorkingProfile) -> Result<(), Box<dyn Er
     // Prompt the user to choose a mod to edit
     let mod_to_edit = select("Select a mod to edit", menu).await?;
 
-    // Parameters for the request
-    let params = &[
-        (
-            "loaders",
-            &serde_json::to_string(&[&working_profile.profile.loader])?,
-        ),
-        (
-            "game_versions",
-            &serde_json::to_string(&[&working_profile.profile.gameversion])?,
-        ),
-    ];
-
-    // Parse the response
-    let parsed = list_versions(working_profile, mod_to_edit.project_id.clone(), params).await?;
+    // Get all compatible versions
+    let parsed = list_versions(working_profile, mod_to_edit.project_id.clone()).await?;
 
     /*
         Create a list of available versions.
@@ -529,7 +656,9 @@ pub async fn edit_mod(working_profile: &WorkingProfile) -> Result<(), Box<dyn Er
 
     // Check if the selected mod version equals to already installed one
     if version_to_install.version_id == mod_to_edit.version_id {
-        return Err("This mod is already installed".into());
+        return Err(MineFetchError::Invalid(
+            "This mod is already installed".into(),
+        ));
     }
 
     replace_mods(
@@ -555,12 +684,21 @@ pub async fn replace_mods(
     old_hashes: Vec<&String>,
     new_mods: Vec<Anymod>,
     working_profile: &WorkingProfile,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), MineFetchError> {
+    let new_hashes: Vec<String> = new_mods.iter().map(|anymod| anymod.hash.clone()).collect();
+
     // Download the new ones
-    download_multiple_mods(new_mods, Arc::new(working_profile.clone())).await?;
+    let downloads = download_mods_reported(new_mods, Arc::new(working_profile.clone())).await?;
 
-    // Remove the old mods
-    remove_mods_by_hash(&working_profile.profile.modsfolder, &old_hashes).await?;
+    // An old file goes away only if its new one is in place, a failed download keeps the old mod
+    let replaced: Vec<&String> = old_hashes
+        .into_iter()
+        .zip(&new_hashes)
+        .filter(|(_, new_hash)| downloads.done.contains(*new_hash))
+        .map(|(old_hash, _)| old_hash)
+        .collect();
 
-    Ok(())
+    remove_mods_by_hash(&working_profile.profile.modsfolder, &replaced).await?;
+
+    downloads.into_result()
 }
@@ -1,18 +1,17 @@
 // Standard libraries
-use std::collections::HashSet;
-use std::error::Error;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 // External imports
 use serde::{Deserialize, Serialize};
 use tokio::fs::read_to_string;
-use tokio::fs::write;
-use toml::from_str;
 use toml::to_string;
 
 // Internal modules
-use crate::structs::Hash;
-use crate::utils::get_hashes;
+use crate::error::MineFetchError;
+use crate::migrate::{CACHE_SCHEMA_VERSION, Schema, load};
+use crate::structs::{Hash, Profile};
+use crate::utils::{atomic_write, get_hashed_jars, get_hashes};
 use crate::{
     api::{Anymod, get_mods_from_hash},
     structs::WorkingProfile,
@@ -20,14 +19,18 @@ use crate::{
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Cache {
+    /// Format version of the file (see migrate.rs)
+    #[serde(default)]
+    schema_version: u32,
     elements: Vec<Anymod>,
 }
 
 impl Cache {
     fn new() -> Self {
-        return Self {
+        Self {
+            schema_version: CACHE_SCHEMA_VERSION,
             elements: Vec::new(),
-        };
+        }
     }
 }
 
@@ -37,11 +40,11 @@ pub async fn cache_profile(
     mut cache: Cache,
     new_mods: Option<Vec<String>>,
     old_mods: Option<Vec<String>>,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), MineFetchError> {
     // Add new mods
     if let Some(new_mods) = new_mods {
         let hash = Hash {
-            hashes: new_mods.iter().map(|hash| hash.clone()).collect(),
+            hashes: new_mods.to_vec(),
             algorithm: "sha1".to_string(),
             loaders: None,
             game_versions: None,
@@ -58,7 +61,7 @@ pub async fn cache_profile(
     if let Some(old_mods) = old_mods {
         cache
             .elements
-            .retain(|element| !old_mods.contains(&&element.hash));
+            .retain(|element| !old_mods.contains(&element.hash));
     }
 
     // Sort the result
@@ -75,25 +78,35 @@ pub async fn cache_profile(
 pub async fn write_cache(
     working_profile: &WorkingProfile,
     cache: Cache,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), MineFetchError> {
     let path = Path::new(&working_profile.profile.modsfolder).join("cache.toml");
 
-    write(path, to_string(&cache)?).await?;
-    Ok(())
+    atomic_write(&path, to_string(&cache)?).await
 }
 
 /// Reads cache from the selected profile
-pub async fn read_cache(working_profile: &WorkingProfile) -> Result<Cache, Box<dyn Error>> {
-    let path = Path::new(&working_profile.profile.modsfolder).join("cache.toml");
-    if let Ok(file) = read_to_string(path).await {
-        let parsed: Cache = from_str(&file)?;
+pub async fn read_cache(working_profile: &WorkingProfile) -> Result<Cache, MineFetchError> {
+    read_profile_cache(&working_profile.profile).await
+}
+
+/// Mods from cache.toml as they are, without looking at the folder
+pub async fn read_cached_mods(profile: &Profile) -> Result<Vec<Anymod>, MineFetchError> {
+    Ok(read_profile_cache(profile).await?.elements)
+}
+
+/// Reads cache.toml of any profile (no client needed)
+async fn read_profile_cache(profile: &Profile) -> Result<Cache, MineFetchError> {
+    let path = Path::new(&profile.modsfolder).join("cache.toml");
+    if let Ok(file) = read_to_string(&path).await {
+        // Older versions are upgraded
+        let parsed: Cache = load(&path, &file, Schema::Cache).await?;
         return Ok(parsed);
     }
     Ok(Cache::new())
 }
 
 /// Validates cache in the selected profile and rewrites it if needed
-pub async fn validate_cache(working_profile: &WorkingProfile) -> Result<(), Box<dyn Error>> {
+pub async fn validate_cache(working_profile: &WorkingProfile) -> Result<(), MineFetchError> {
     if let Ok(real_hashes) = get_hashes(&working_profile.profile.modsfolder).await {
         if let Ok(cache) = read_cache(working_profile).await {
             // Create a HashSet for these lists
@@ -120,7 +133,54 @@ pub async fn validate_cache(working_profile: &WorkingProfile) -> Result<(), Box<
 
 pub async fn list_mods_cached(
     working_profile: &WorkingProfile,
-) -> Result<Vec<Anymod>, Box<dyn Error>> {
-    validate_cache(working_profile).await?;
-    Ok(read_cache(working_profile).await?.elements)
+) -> Result<Vec<Anymod>, MineFetchError> {
+    if working_profile.options.offline {
+        return list_mods_offline(working_profile).await;
+    }
+
+    match validate_cache(working_profile).await {
+        Ok(_) => Ok(read_cache(working_profile).await?.elements),
+
+        // Fall back to the local data if Modrinth can't be reached
+        Err(MineFetchError::Network { .. }) => {
+            eprintln!(":wrn: Modrinth is unreachable, working offline");
+            list_mods_offline(working_profile).await
+        }
+        Err(MineFetchError::Status { status, .. }) if status >= 500 => {
+            eprintln!(":wrn: Modrinth is unavailable (HTTP {status}), working offline");
+            list_mods_offline(working_profile).await
+        }
+
+        Err(error) => Err(error),
+    }
+}
+
+/// Lists mods using only cache.toml and the jars themselves.
+/// Jars that aren't in the cache are returned as unresolved.
+/// Nothing is written (callers may hold only a shared lock),
+/// the next online run finds added and removed jars by their hashes
+pub async fn list_mods_offline(
+    working_profile: &WorkingProfile,
+) -> Result<Vec<Anymod>, MineFetchError> {
+    let cache = read_cache(working_profile).await?;
+    let jars = get_hashed_jars(&working_profile.profile.modsfolder).await?;
+
+    let known: HashMap<&String, &Anymod> = cache
+        .elements
+        .iter()
+        .map(|element| (&element.hash, element))
+        .collect();
+
+    let mut result: Vec<Anymod> = Vec::new();
+
+    for (path, hash) in jars {
+        match known.get(&hash) {
+            Some(anymod) => result.push((*anymod).clone()),
+            None => result.push(Anymod::unresolved(&path, hash)),
+        }
+    }
+
+    result.sort_by_key(|element| element.title.clone().unwrap_or(element.filename.clone()));
+
+    Ok(result)
 }
@@ -0,0 +1,663 @@
+/*
+  ____ _     ___
+ / ___| |   |_ _|
+| |   | |    | |
+| |___| |___ | |
+ \____|_____|___|
+
+*/
+
+// Standard imports
+use std::path::PathBuf;
+
+// Internal modules
+use crate::consts::{COMMANDS, DEFAULT_SEARCH_LIMIT, GLOBAL_FLAGS, SEARCH_SORTS, SHELLS};
+use crate::error::MineFetchError;
+use crate::structs::{Options, ProfileClone, ProfileEdit, SearchQuery, SyncArgs};
+use crate::utils::{set_config_override, set_verbose};
+
+/// A command or a subcommand
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub args: &'static [ArgSpec],
+    pub description: &'static str,
+    pub flags: &'static [FlagSpec],
+    pub subcommands: &'static [CommandSpec],
+
+    /// Not shown in the help
+    pub hidden: bool,
+}
+
+/// A positional argument
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub required: bool,
+
+    /// Takes all the remaining arguments
+    pub many: bool,
+}
+
+/// A flag of a command (or a global one)
+pub struct FlagSpec {
+    pub name: &'static str,
+    pub short: Option<char>,
+
+    /// The placeholder of the value, None for switches
+    pub value: Option<&'static str>,
+    pub description: &'static str,
+}
+
+impl CommandSpec {
+    /// '<mod>', '[path]', '[mod]...'
+    pub fn usage_args(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| {
+                let name = match arg.required {
+                    true => format!("<{}>", arg.name),
+                    false => format!("[{}]", arg.name),
+                };
+
+                match arg.many {
+                    true => format!("{name}..."),
+                    false => name,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Checks the name and the aliases
+    pub fn is_called(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+}
+
+impl FlagSpec {
+    /// '--limit <n>'
+    pub fn usage(&self) -> String {
+        let short = self
+            .short
+            .map(|short| format!("-{short}, "))
+            .unwrap_or_default();
+
+        match self.value {
+            Some(value) => format!("{short}{} <{value}>", self.name),
+            None => format!("{short}{}", self.name),
+        }
+    }
+}
+
+/// Everything the user asked for, checked and typed
+pub enum Command {
+    /// No arguments at all
+    Nothing,
+
+    /// The help of a command (empty for the list of commands)
+    Help(Vec<&'static CommandSpec>),
+
+    Add(String),
+    Info(String),
+    Search(SearchQuery),
+
+    ProfileCreate,
+    ProfileDelete {
+        all: bool,
+    },
+    ProfileSwitch,
+    ProfileList,
+    ProfileEdit(ProfileEdit),
+    ProfileClone(ProfileClone),
+    ProfileImport(Option<String>),
+
+    Update {
+        include: Vec<String>,
+        exclude: Vec<String>,
+    },
+    Outdated,
+    Changelog(Option<String>),
+    List,
+    Sync(SyncArgs),
+    Install {
+        lockfile: Option<String>,
+    },
+
+    LoaderInstall {
+        version: Option<String>,
+    },
+    LoaderUpdate,
+
+    LockAdd,
+    LockRemove,
+    LockList,
+    LockGenerate {
+        output: Option<String>,
+    },
+
+    Edit,
+    Version,
+
+    /// The completion script for a shell
+    Completions(String),
+
+    /// Candidates for the last word (used by the completion scripts)
+    Complete(Vec<String>),
+    Debug,
+}
+
+/// What the command line looks like before it's typed
+struct Parsed {
+    path: Vec<&'static CommandSpec>,
+    args: Vec<String>,
+    flags: Vec<(&'static str, Option<String>)>,
+}
+
+impl Parsed {
+    /// True if the switch was given
+    fn has(&self, name: &str) -> bool {
+        self.flags.iter().any(|(flag, _)| *flag == name)
+    }
+
+    /// The last value of the flag
+    fn value(&self, name: &str) -> Option<String> {
+        self.values(name).pop()
+    }
+
+    /// All values of a flag that can be repeated
+    fn values(&self, name: &str) -> Vec<String> {
+        self.flags
+            .iter()
+            .filter(|(flag, _)| *flag == name)
+            .filter_map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    /// The first positional argument
+    fn arg(&self) -> Option<String> {
+        self.args.first().cloned()
+    }
+}
+
+/// Reads the command line (without the program name)
+pub fn parse_args(args: &[String]) -> Result<(Command, Options), MineFetchError> {
+    let mut options = Options::default();
+    let mut parsed = Parsed {
+        path: Vec::new(),
+        args: Vec::new(),
+        flags: Vec::new(),
+    };
+
+    let mut help = false;
+    let mut only_args = false;
+    let mut level: &'static [CommandSpec] = COMMANDS;
+
+    let mut tokens = args.iter();
+
+    while let Some(token) = tokens.next() {
+        // Commands and positional arguments
+        if only_args || !token.starts_with('-') || token == "-" {
+            // A (sub)command is expected while there are subcommands
+            if parsed.args.is_empty() && !level.is_empty() {
+                let command = level
+                    .iter()
+                    .find(|command| command.is_called(token))
+                    .ok_or_else(|| unknown_command(&parsed.path, token, level))?;
+
+                parsed.path.push(command);
+                level = command.subcommands;
+                continue;
+            }
+
+            parsed.args.push(token.clone());
+            continue;
+        }
+
+        // Everything after '--' is an argument
+        if token == "--" {
+            only_args = true;
+            continue;
+        }
+
+        // Both '--flag value' and '--flag=value' are accepted
+        let (name, inline) = match token.split_once('=') {
+            Some((name, value)) if token.starts_with("--") => (name, Some(value.to_string())),
+            _ => (token.as_str(), None),
+        };
+
+        if name == "--help" || name == "-h" {
+            help = true;
+            continue;
+        }
+
+        let command_flags = parsed
+            .path
+            .last()
+            .map(|command| command.flags)
+            .unwrap_or(&[]);
+
+        let (flag, global) = match find_flag(command_flags, name) {
+            Some(flag) => (flag, false),
+            None => match find_flag(GLOBAL_FLAGS, name) {
+                Some(flag) => (flag, true),
+                None => return Err(unknown_flag(&parsed.path, name, command_flags)),
+            },
+        };
+
+        let value = match (flag.value, inline) {
+            (Some(_), Some(value)) => Some(value),
+            (Some(_), None) => tokens.next().cloned(),
+            (None, Some(_)) => {
+                return Err(MineFetchError::Invalid(format!(
+                    "{} doesn't take a value",
+                    flag.name
+                )));
+            }
+            (None, None) => None,
+        };
+
+        if flag.value.is_some() && value.as_deref().is_none_or(str::is_empty) {
+            return Err(MineFetchError::Invalid(format!(
+                "{} needs a value ({})",
+                flag.name,
+                flag.usage()
+            )));
+        }
+
+        match global {
+            true => apply_global(flag.name, value, &mut options),
+            false => parsed.flags.push((flag.name, value)),
+        }
+    }
+
+    if options.all_profiles && options.profile.is_some() {
+        return Err(MineFetchError::Invalid(
+            "--profile and --all-profiles can't be used together".into(),
+        ));
+    }
+
+    // 'minefetch help profile edit'
+    if let Some(command) = parsed.path.first()
+        && command.name == "help"
+    {
+        return Ok((Command::Help(find_path(&parsed.args)?), options));
+    }
+
+    if help {
+        return Ok((Command::Help(parsed.path), options));
+    }
+
+    // 'minefetch profile' shows what 'profile' can do
+    if !level.is_empty() && !parsed.path.is_empty() {
+        return Ok((Command::Help(parsed.path), options));
+    }
+
+    check_args(&parsed)?;
+
+    Ok((into_command(parsed)?, options))
+}
+
+/// Sets a global option
+fn apply_global(name: &str, value: Option<String>, options: &mut Options) {
+    match name {
+        "--profile" => options.profile = value,
+        "--all-profiles" => options.all_profiles = true,
+        "--json" => options.json = true,
+        "--yes" => options.yes = true,
+        "--offline" => options.offline = true,
+        "--refresh" => options.refresh = true,
+        "--verbose" => set_verbose(),
+        "--config" => {
+            if let Some(path) = value {
+                set_config_override(PathBuf::from(path));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Finds a flag by its long or short name
+pub fn find_flag(flags: &'static [FlagSpec], name: &str) -> Option<&'static FlagSpec> {
+    flags.iter().find(|flag| {
+        flag.name == name
+            || flag
+                .short
+                .is_some_and(|short| name.strip_prefix('-') == Some(short.to_string().as_str()))
+    })
+}
+
+/// Turns command names into specs, for 'minefetch help <command>'
+pub fn find_path(names: &[String]) -> Result<Vec<&'static CommandSpec>, MineFetchError> {
+    let mut path: Vec<&'static CommandSpec> = Vec::new();
+    let mut level: &'static [CommandSpec] = COMMANDS;
+
+    for name in names {
+        let command = level
+            .iter()
+            .find(|command| command.is_called(name))
+            .ok_or_else(|| unknown_command(&path, name, level))?;
+
+        path.push(command);
+        level = command.subcommands;
+    }
+
+    Ok(path)
+}
+
+/// Checks the number of positional arguments
+fn check_args(parsed: &Parsed) -> Result<(), MineFetchError> {
+    let Some(command) = parsed.path.last() else {
+        return Ok(());
+    };
+
+    let many = command.args.iter().any(|arg| arg.many);
+
+    if !many && parsed.args.len() > command.args.len() {
+        return Err(MineFetchError::Invalid(format!(
+            "Unexpected argument '{}'. Usage: {}",
+            parsed.args[command.args.len()],
+            usage(&parsed.path)
+        )));
+    }
+
+    if let Some(missing) = command
+        .args
+        .iter()
+        .skip(parsed.args.len())
+        .find(|arg| arg.required)
+    {
+        return Err(MineFetchError::Invalid(format!(
+            "Missing <{}>. Usage: {}",
+            missing.name,
+            usage(&parsed.path)
+        )));
+    }
+
+    Ok(())
+}
+
+/// 'minefetch profile clone'
+pub fn command_name(path: &[&CommandSpec]) -> String {
+    let mut name = String::from("minefetch");
+
+    for command in path {
+        name.push(' ');
+        name.push_str(command.name);
+    }
+
+    name
+}
+
+/// 'minefetch profile clone <name> [flags]'
+pub fn usage(path: &[&CommandSpec]) -> String {
+    let mut usage = command_name(path);
+
+    if let Some(command) = path.last() {
+        let args = command.usage_args();
+
+        if !args.is_empty() {
+            usage.push(' ');
+            usage.push_str(&args);
+        }
+
+        if !command.flags.is_empty() {
+            usage.push_str(" [flags]");
+        }
+    }
+
+    usage
+}
+
+/// Turns the checked command line into a Command
+fn into_command(parsed: Parsed) -> Result<Command, MineFetchError> {
+    let names: Vec<&str> = parsed.path.iter().map(|command| command.name).collect();
+
+    let command = match names.as_slice() {
+        [] => Command::Nothing,
+
+        ["add"] => Command::Add(parsed.args[0].clone()),
+        ["info"] => Command::Info(parsed.args[0].clone()),
+        ["search"] => Command::Search(search_query(&parsed)?),
+
+        ["profile", "create"] => Command::ProfileCreate,
+        ["profile", "delete"] => match parsed.arg().as_deref() {
+            None => Command::ProfileDelete { all: false },
+            Some("all") => Command::ProfileDelete { all: true },
+            Some(other) => {
+                return Err(MineFetchError::Invalid(format!(
+                    "Unexpected argument '{other}'. Usage: {}",
+                    usage(&parsed.path)
+                )));
+            }
+        },
+        ["profile", "switch"] => Command::ProfileSwitch,
+        ["profile", "list"] => Command::ProfileList,
+        ["profile", "edit"] => Command::ProfileEdit(ProfileEdit {
+            name: parsed.value("--name"),
+            modsfolder: parsed.value("--dir"),
+            gameversion: parsed.value("--game-version"),
+            loader: parsed.value("--loader"),
+            resolve: parsed.has("--resolve"),
+        }),
+        ["profile", "clone"] => Command::ProfileClone(ProfileClone {
+            source: parsed.args[0].clone(),
+            modsfolder: parsed.value("--dir").ok_or_else(|| {
+                MineFetchError::Invalid(format!(
+                    "Missing --dir. Usage: {} <name> --dir <path>",
+                    command_name(&parsed.path)
+                ))
+            })?,
+            name: parsed.value("--name"),
+            gameversion: parsed.value("--game-version"),
+        }),
+        ["profile", "import-instance"] => Command::ProfileImport(parsed.arg()),
+
+        ["update"] => Command::Update {
+            include: parsed.args.clone(),
+            exclude: parsed.values("--exclude"),
+        },
+        ["outdated"] => Command::Outdated,
+        ["changelog"] => Command::Changelog(parsed.arg()),
+        ["list"] => Command::List,
+        ["sync"] => Command::Sync(sync_args(&parsed)?),
+        ["install"] => {
+            if !parsed.has("--locked") {
+                return Err(MineFetchError::Invalid(
+                    "Usage: minefetch install --locked (use 'minefetch add' to add mods)".into(),
+                ));
+            }
+
+            Command::Install {
+                lockfile: parsed.value("--lockfile"),
+            }
+        }
+
+        ["loader", "install"] => Command::LoaderInstall {
+            version: parsed.value("--version"),
+        },
+        ["loader", "update"] => Command::LoaderUpdate,
+
+        ["lock", "add"] => Command::LockAdd,
+        ["lock", "remove"] => Command::LockRemove,
+        ["lock", "list"] => Command::LockList,
+        ["lock", "generate"] => Command::LockGenerate {
+            output: parsed.value("--output"),
+        },
+
+        ["edit"] => Command::Edit,
+        ["version"] => Command::Version,
+        ["completions"] => {
+            let shell = parsed.args[0].clone();
+
+            if !SHELLS.contains(&shell.as_str()) {
+                return Err(MineFetchError::Invalid(format!(
+                    "Unknown shell {shell} (use {})",
+                    SHELLS.join(", ")
+                )));
+            }
+
+            Command::Completions(shell)
+        }
+        ["__complete"] => Command::Complete(parsed.args),
+        ["debug"] => Command::Debug,
+
+        // Every command in COMMANDS has its own arm
+        _ => {
+            return Err(MineFetchError::Invalid(format!(
+                "'{}' isn't implemented",
+                usage(&parsed.path)
+            )));
+        }
+    };
+
+    Ok(command)
+}
+
+/// Reads search flags, the arguments are the query
+fn search_query(parsed: &Parsed) -> Result<SearchQuery, MineFetchError> {
+    let search = SearchQuery {
+        query: parsed.args.join(" "),
+        limit: match parsed.value("--limit") {
+            Some(value) => parse_number("--limit", &value)?,
+            None => DEFAULT_SEARCH_LIMIT,
+        },
+        page: match parsed.value("--page") {
+            Some(value) => parse_number("--page", &value)?,
+            None => 1,
+        },
+        sort: parsed.value("--sort").unwrap_or("relevance".into()),
+        categories: parsed.values("--category"),
+        author: parsed.value("--author"),
+    };
+
+    if !SEARCH_SORTS.contains(&search.sort.as_str()) {
+        return Err(MineFetchError::Invalid(format!(
+            "Unknown sorting '{}', use one of: {}",
+            search.sort,
+            SEARCH_SORTS.join(", ")
+        )));
+    }
+
+    if search.limit > 100 {
+        return Err(MineFetchError::Invalid(
+            "Modrinth returns at most 100 results per page".into(),
+        ));
+    }
+
+    Ok(search)
+}
+
+/// Parses a positive number from a flag value
+fn parse_number(flag: &str, value: &str) -> Result<usize, MineFetchError> {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(MineFetchError::Invalid(format!(
+            "{flag} needs a positive number, got '{value}'"
+        ))),
+    }
+}
+
+/// Reads sync flags
+fn sync_args(parsed: &Parsed) -> Result<SyncArgs, MineFetchError> {
+    let side = parsed.value("--side");
+
+    if let Some(side) = &side
+        && side != "client"
+        && side != "server"
+    {
+        return Err(MineFetchError::Invalid(format!(
+            "Unknown side {side} (use client or server)"
+        )));
+    }
+
+    Ok(SyncArgs {
+        manifest: parsed.value("--manifest"),
+        side,
+        dry_run: parsed.has("--dry-run"),
+    })
+}
+
+/// The error for a mistyped command, with the closest one
+fn unknown_command(path: &[&CommandSpec], name: &str, level: &[CommandSpec]) -> MineFetchError {
+    let candidates = level
+        .iter()
+        .filter(|command| !command.hidden)
+        .flat_map(|command| std::iter::once(&command.name).chain(command.aliases));
+
+    let hint = match suggest(name, candidates.copied()) {
+        Some(suggestion) => format!(", did you mean '{suggestion}'?"),
+        None => String::new(),
+    };
+
+    let context = match path.is_empty() {
+        true => String::new(),
+        false => format!(" for '{}'", command_name(path)),
+    };
+
+    MineFetchError::Invalid(format!(
+        "Unknown command '{name}'{context}{hint} (see {} --help)",
+        command_name(path)
+    ))
+}
+
+/// The error for a mistyped flag, with the closest one
+fn unknown_flag(path: &[&CommandSpec], name: &str, flags: &[FlagSpec]) -> MineFetchError {
+    let candidates = flags
+        .iter()
+        .chain(GLOBAL_FLAGS)
+        .map(|flag| flag.name)
+        .chain(["--help"]);
+
+    let hint = match suggest(name, candidates) {
+        Some(suggestion) => format!(", did you mean '{suggestion}'?"),
+        None => String::new(),
+    };
+
+    MineFetchError::Invalid(format!(
+        "Unknown flag '{name}' for '{}'{hint} (see {} --help)",
+        command_name(path),
+        command_name(path)
+    ))
+}
+
+/// Picks the candidate that's closest to the word (if any is close enough)
+fn suggest<'a>(word: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|candidate| {
+            // A prefix is as good as one typo
+            let distance = match candidate.starts_with(word) && word.len() > 2 {
+                true => 1,
+                false => edit_distance(word, candidate),
+            };
+
+            (distance, candidate)
+        })
+        .filter(|(distance, candidate)| {
+            *distance <= (candidate.len() / 3).max(2) && *distance < word.len()
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, right_char) in right.iter().enumerate() {
+            let cost = usize::from(left_char != *right_char);
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+
+        previous = current;
+    }
+
+    previous[right.len()]
+}
@@ -0,0 +1,286 @@
+/*
+  ____                      _      _   _
+ / ___|___  _ __ ___  _ __ | | ___| |_(_) ___  _ __
+| |   / _ \| '_ ` _ \| '_ \| |/ _ \ __| |/ _ \| '_ \
+| |__| (_) | | | | | | |_) | |  __/ |_| | (_) | | | |
+ \____\___/|_| |_| |_| .__/|_|\___|\__|_|\___/|_| |_|
+                     |_|
+
+*/
+
+// Standard imports
+use std::path::PathBuf;
+
+// Internal modules
+use crate::api::Anymod;
+use crate::cache::read_cached_mods;
+use crate::cli::{CommandSpec, FlagSpec, find_flag};
+use crate::consts::{COMMANDS, GLOBAL_FLAGS, LOADERS, SEARCH_SORTS, SHELLS};
+use crate::profile::{get_locks, read_full_config, select_profile};
+use crate::structs::{Options, Profile};
+use crate::utils::set_config_override;
+
+// Every script asks 'minefetch __complete' for the candidates,
+// so profiles and mods are always up to date.
+// When there are none, the shell completes file names
+
+const BASH_SCRIPT: &str = r#"# bash completion for minefetch
+# Add to ~/.bashrc: source <(minefetch completions bash)
+
+_minefetch() {
+    local IFS=$'\n'
+    local candidate
+    COMPREPLY=()
+
+    for candidate in $(minefetch __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null); do
+        COMPREPLY+=("$(printf '%q' "$candidate")")
+    done
+}
+
+complete -o default -F _minefetch minefetch
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef minefetch
+# zsh completion for minefetch
+# Add to ~/.zshrc (after compinit): source <(minefetch completions zsh)
+# or save it as _minefetch in a directory from $fpath
+
+_minefetch() {
+    local -a candidates
+    candidates=("${(@f)$(minefetch __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+    else
+        _files
+    fi
+}
+
+if [[ "${funcstack[1]}" == "_minefetch" ]]; then
+    _minefetch "$@"
+else
+    compdef _minefetch minefetch
+fi
+"#;
+
+const FISH_SCRIPT: &str = r#"# fish completion for minefetch
+# Save it: minefetch completions fish > ~/.config/fish/completions/minefetch.fish
+
+function __minefetch_complete
+    set -l candidates (minefetch __complete -- (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null)
+
+    if test (count $candidates) -gt 0
+        printf '%s\n' $candidates
+    else
+        __fish_complete_path (commandline -ct)
+    end
+end
+
+complete -c minefetch -f -a '(__minefetch_complete)'
+"#;
+
+/// Prints the completion script (the shell is checked by the parser)
+pub fn print_script(shell: &str) {
+    match shell {
+        "bash" => print!("{BASH_SCRIPT}"),
+        "zsh" => print!("{ZSH_SCRIPT}"),
+        _ => print!("{FISH_SCRIPT}"),
+    }
+}
+
+/// Prints candidates for the last word, one per line.
+/// Errors are never shown, a broken config just gives no candidates
+pub async fn complete(words: &[String]) {
+    let (current, before) = match words.split_last() {
+        Some((current, before)) => (unquote(current), before),
+        None => (String::new(), words),
+    };
+
+    let mut path: Vec<&'static CommandSpec> = Vec::new();
+    let mut level: &'static [CommandSpec] = COMMANDS;
+    let mut args: Vec<&str> = Vec::new();
+    let mut pending: Option<&'static FlagSpec> = None;
+    let mut only_args = false;
+    let mut options = Options::default();
+
+    // Walk the line like the parser does, but never fail
+    for word in before {
+        // The value of the previous flag
+        if let Some(flag) = pending.take() {
+            match flag.name {
+                "--profile" => options.profile = Some(word.clone()),
+                "--config" => set_config_override(PathBuf::from(word)),
+                _ => {}
+            }
+            continue;
+        }
+
+        if !only_args && word == "--" {
+            only_args = true;
+            continue;
+        }
+
+        if !only_args && word.starts_with('-') && word != "-" {
+            let flags = path.last().map(|command| command.flags).unwrap_or(&[]);
+
+            // '--flag=value' already has its value
+            if let Some(flag) = find_flag(flags, word).or_else(|| find_flag(GLOBAL_FLAGS, word))
+                && flag.value.is_some()
+            {
+                pending = Some(flag);
+            }
+            continue;
+        }
+
+        if args.is_empty()
+            && let Some(command) = level.iter().find(|command| command.is_called(word))
+        {
+            path.push(command);
+            level = command.subcommands;
+            continue;
+        }
+
+        args.push(word);
+    }
+
+    let candidates = match pending {
+        Some(flag) => flag_values(flag, &options).await,
+
+        // Flags of the command and global ones
+        None if !only_args && current.starts_with('-') => path
+            .last()
+            .map(|command| command.flags)
+            .unwrap_or(&[])
+            .iter()
+            .chain(GLOBAL_FLAGS)
+            .map(|flag| flag.name.to_string())
+            .chain(["--help".to_string()])
+            .collect(),
+
+        // A (sub)command is expected
+        None if args.is_empty() && !level.is_empty() => command_names(level),
+
+        None => arg_values(&path, &args, &options).await,
+    };
+
+    let mut shown: Vec<String> = Vec::new();
+
+    for candidate in candidates {
+        if candidate.starts_with(&current) && !shown.contains(&candidate) {
+            println!("{candidate}");
+            shown.push(candidate);
+        }
+    }
+}
+
+/// Values of a flag that takes one
+async fn flag_values(flag: &FlagSpec, options: &Options) -> Vec<String> {
+    match flag.name {
+        "--profile" => profile_names().await,
+        "--loader" => LOADERS.iter().map(|(_, name)| name.to_string()).collect(),
+        "--sort" => SEARCH_SORTS.iter().map(|sort| sort.to_string()).collect(),
+        "--side" => vec!["client".into(), "server".into()],
+        "--exclude" => mod_names(&installed_mods(options).await),
+
+        // Paths, versions and free text
+        _ => Vec::new(),
+    }
+}
+
+/// Positional arguments of a command
+async fn arg_values(path: &[&CommandSpec], args: &[&str], options: &Options) -> Vec<String> {
+    let names: Vec<&str> = path.iter().map(|command| command.name).collect();
+
+    match (names.as_slice(), args.len()) {
+        (["changelog"], 0) => mod_names(&installed_mods(options).await),
+        (["profile", "clone"], 0) => profile_names().await,
+        (["profile", "delete"], 0) => vec!["all".into()],
+        (["completions"], 0) => SHELLS.iter().map(|shell| shell.to_string()).collect(),
+
+        // Locked mods aren't updated
+        (["update"], _) => {
+            let (_, unlocked) = locked_split(options).await.unwrap_or_default();
+            mod_names(&unlocked)
+        }
+
+        // 'minefetch help profile <command>'
+        (["help"], _) => {
+            let mut level: &'static [CommandSpec] = COMMANDS;
+
+            for arg in args {
+                match level.iter().find(|command| command.is_called(arg)) {
+                    Some(command) => level = command.subcommands,
+                    None => return Vec::new(),
+                }
+            }
+
+            command_names(level)
+        }
+
+        _ => Vec::new(),
+    }
+}
+
+/// Names of the commands that are shown in the help
+fn command_names(level: &[CommandSpec]) -> Vec<String> {
+    level
+        .iter()
+        .filter(|command| !command.hidden)
+        .map(|command| command.name.to_string())
+        .collect()
+}
+
+/// Names of all profiles from config.toml
+async fn profile_names() -> Vec<String> {
+    match read_full_config().await {
+        Ok(config) => config
+            .profile
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The profile the command would use (--profile, .minefetch.toml or the active one)
+async fn completion_profile(options: &Options) -> Option<Profile> {
+    let config = read_full_config().await.unwrap_or_default();
+
+    select_profile(&config.profile, options).await.ok()
+}
+
+/// Mods from cache.toml of the profile
+async fn installed_mods(options: &Options) -> Vec<Anymod> {
+    match completion_profile(options).await {
+        Some(profile) => read_cached_mods(&profile).await.unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+/// Installed mods split into locked (locks.toml) and the rest
+async fn locked_split(options: &Options) -> Option<(Vec<Anymod>, Vec<Anymod>)> {
+    let profile = completion_profile(options).await?;
+    let mods = read_cached_mods(&profile).await.ok()?;
+    let locks = get_locks(&profile).await.unwrap_or_default();
+
+    Some(
+        mods.into_iter()
+            .partition(|anymod| locks.contains(&anymod.hash)),
+    )
+}
+
+/// Titles, find_installed understands them
+fn mod_names(mods: &[Anymod]) -> Vec<String> {
+    let mut names: Vec<String> = mods
+        .iter()
+        .filter_map(|anymod| anymod.title.clone())
+        .collect();
+
+    names.sort();
+    names
+}
+
+/// The word as it was typed, without the shell quoting
+fn unquote(word: &str) -> String {
+    word.trim_start_matches(['\'', '"']).replace("\\ ", " ")
+}
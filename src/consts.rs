@@ -7,82 +7,499 @@
 
 */
 
-// Import strctures from helpmsg
-use crate::helpmsg::{Help, Message};
+// Import structures of the command tree
+use crate::cli::{ArgSpec, CommandSpec, FlagSpec};
 
 // Program name
-pub const NAME: &'static str = "MineFetch";
+pub const NAME: &str = "MineFetch";
 
 // Program version
-pub const PROGRAM_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+pub const PROGRAM_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // User Agent which is used in all requests
-pub const USER_AGENT: &'static str = concat!(
+pub const USER_AGENT: &str = concat!(
     "KirillkoTankisto/minefetch/",
     env!("CARGO_PKG_VERSION"),
     " (kirsergeev@icloud.com)"
 );
 
-// Help message structure
-pub const HELP_MESSAGE: Help = Help {
-    header: "Commands:",
-    program_name: "minefetch",
-    message: &[
-        &Message {
-            name: "search",
-            description: "search for mods and install them",
-        },
-        &Message {
-            name: "add",
-            description: "add a single mod",
-        },
-        &Message {
-            name: "profile create",
-            description: "create a new profile",
-        },
-        &Message {
-            name: "profile delete",
-            description: "delete a selected profile",
-        },
-        &Message {
-            name: "profile delete all",
-            description: "delete all profiles",
-        },
-        &Message {
-            name: "profile switch",
-            description: "switch between profiles",
-        },
-        &Message {
-            name: "profile list",
-            description: "list all profiles",
-        },
-        &Message {
-            name: "update",
-            description: "update all mods",
-        },
-        &Message {
-            name: "upgrade",
-            description: "same as 'minefetch update'",
-        },
-        &Message {
-            name: "list",
-            description: "list installed mods",
-        },
-        &Message {
-            name: "lock add",
-            description: "add a lock which stops selected mod from updating",
-        },
-        &Message {
-            name: "lock remove",
-            description: "delete a lock",
-        },
-        &Message {
-            name: "lock list",
-            description: "list locks",
-        },
-        &Message {
-            name: "version",
-            description: "display MineFetch version",
-        },
-    ],
-};
+// How long to wait for a connection to the server (seconds)
+pub const CONNECT_TIMEOUT_SECS: u64 = 10;
+
+// How long to wait for the next piece of the response (seconds)
+pub const READ_TIMEOUT_SECS: u64 = 30;
+
+// How many times a failed request is repeated
+pub const MAX_RETRIES: u32 = 3;
+
+// The first pause between retries, doubles every time (milliseconds)
+pub const BACKOFF_BASE_MS: u64 = 500;
+
+// How many files are downloaded at once if 'jobs' isn't set
+pub const DEFAULT_JOBS: usize = 8;
+
+// How long Modrinth metadata is cached if 'cache_ttl' isn't set (seconds)
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 60 * 60;
+
+// Cached Modrinth metadata that wasn't fetched for this long is deleted (seconds)
+pub const METACACHE_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+// How many search results are shown by default
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+// Sorting methods that Modrinth search supports
+pub const SEARCH_SORTS: [&str; 5] = ["relevance", "downloads", "follows", "newest", "updated"];
+
+// Loaders that a profile can use (display name, Modrinth name)
+pub const LOADERS: [(&str, &str); 4] = [
+    ("Quilt", "quilt"),
+    ("Fabric", "fabric"),
+    ("Forge", "forge"),
+    ("NeoForge", "neoforge"),
+];
+
+// Where loader versions come from if 'loader_meta' doesn't say otherwise
+pub const FABRIC_META: &str = "https://meta.fabricmc.net";
+pub const QUILT_META: &str = "https://meta.quiltmc.org";
+pub const NEOFORGE_MAVEN: &str = "https://maven.neoforged.net";
+
+// Shells that 'minefetch completions' has scripts for
+pub const SHELLS: [&str; 3] = ["bash", "zsh", "fish"];
+
+// How many compatible versions 'minefetch info' shows
+pub const INFO_VERSIONS: usize = 5;
+
+// Flags that work with every command
+pub const GLOBAL_FLAGS: &[FlagSpec] = &[
+    FlagSpec {
+        name: "--profile",
+        short: None,
+        value: Some("name"),
+        description: "use this profile without switching to it",
+    },
+    FlagSpec {
+        name: "--all-profiles",
+        short: None,
+        value: None,
+        description: "run update, outdated or list for every profile",
+    },
+    FlagSpec {
+        name: "--config",
+        short: None,
+        value: Some("file"),
+        description: "use another config file (also MINEFETCH_CONFIG)",
+    },
+    FlagSpec {
+        name: "--json",
+        short: None,
+        value: None,
+        description: "print machine-readable JSON (outdated, --all-profiles)",
+    },
+    FlagSpec {
+        name: "--yes",
+        short: Some('y'),
+        value: None,
+        description: "answer yes to every confirmation",
+    },
+    FlagSpec {
+        name: "--offline",
+        short: None,
+        value: None,
+        description: "work without network using the cache (list, lock)",
+    },
+    FlagSpec {
+        name: "--refresh",
+        short: None,
+        value: None,
+        description: "ignore the cached Modrinth metadata",
+    },
+    FlagSpec {
+        name: "--verbose",
+        short: Some('v'),
+        value: None,
+        description: "print requests and cache hits",
+    },
+];
+
+// A command without arguments, flags and subcommands
+const fn simple(name: &'static str, description: &'static str) -> CommandSpec {
+    CommandSpec {
+        name,
+        aliases: &[],
+        args: &[],
+        description,
+        flags: &[],
+        subcommands: &[],
+        hidden: false,
+    }
+}
+
+// Command tree, it's used both for parsing and for the help
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "search",
+        aliases: &[],
+        args: &[ArgSpec {
+            name: "query",
+            required: false,
+            many: true,
+        }],
+        description: "search for mods and install them",
+        flags: &[
+            FlagSpec {
+                name: "--limit",
+                short: None,
+                value: Some("n"),
+                description: "how many results to show",
+            },
+            FlagSpec {
+                name: "--page",
+                short: None,
+                value: Some("n"),
+                description: "which page of results to show",
+            },
+            FlagSpec {
+                name: "--sort",
+                short: None,
+                value: Some("method"),
+                description: "relevance, downloads, follows, newest or updated",
+            },
+            FlagSpec {
+                name: "--category",
+                short: None,
+                value: Some("name"),
+                description: "show only mods with this category (can be repeated)",
+            },
+            FlagSpec {
+                name: "--author",
+                short: None,
+                value: Some("name"),
+                description: "show only mods of this author",
+            },
+        ],
+        subcommands: &[],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "add",
+        aliases: &[],
+        args: &[ArgSpec {
+            name: "mod",
+            required: true,
+            many: false,
+        }],
+        description: "add a single mod",
+        flags: &[],
+        subcommands: &[],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "info",
+        aliases: &[],
+        args: &[ArgSpec {
+            name: "mod",
+            required: true,
+            many: false,
+        }],
+        description: "show details about a mod",
+        flags: &[],
+        subcommands: &[],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "profile",
+        aliases: &[],
+        args: &[],
+        description: "manage profiles",
+        flags: &[],
+        subcommands: &[
+            simple("create", "create a new profile"),
+            CommandSpec {
+                name: "delete",
+                aliases: &[],
+                args: &[ArgSpec {
+                    name: "all",
+                    required: false,
+                    many: false,
+                }],
+                description: "delete a selected profile ('all' deletes all profiles)",
+                flags: &[],
+                subcommands: &[],
+                hidden: false,
+            },
+            simple("switch", "switch between profiles"),
+            simple("list", "list all profiles"),
+            CommandSpec {
+                name: "edit",
+                aliases: &[],
+                args: &[],
+                description: "edit the selected profile (asks for every field without flags)",
+                flags: &[
+                    FlagSpec {
+                        name: "--name",
+                        short: None,
+                        value: Some("name"),
+                        description: "new name",
+                    },
+                    FlagSpec {
+                        name: "--dir",
+                        short: None,
+                        value: Some("path"),
+                        description: "new mods directory",
+                    },
+                    FlagSpec {
+                        name: "--game-version",
+                        short: None,
+                        value: Some("version"),
+                        description: "new Minecraft version",
+                    },
+                    FlagSpec {
+                        name: "--loader",
+                        short: None,
+                        value: Some("loader"),
+                        description: "new loader (quilt, fabric, forge or neoforge)",
+                    },
+                    FlagSpec {
+                        name: "--resolve",
+                        short: None,
+                        value: None,
+                        description: "re-resolve installed mods without asking",
+                    },
+                ],
+                subcommands: &[],
+                hidden: false,
+            },
+            CommandSpec {
+                name: "clone",
+                aliases: &[],
+                args: &[ArgSpec {
+                    name: "name",
+                    required: true,
+                    many: false,
+                }],
+                description: "copy a profile and its mods",
+                flags: &[
+                    FlagSpec {
+                        name: "--dir",
+                        short: None,
+                        value: Some("path"),
+                        description: "mods directory of the copy (required)",
+                    },
+                    FlagSpec {
+                        name: "--name",
+                        short: None,
+                        value: Some("name"),
+                        description: "name of the copy",
+                    },
+                    FlagSpec {
+                        name: "--game-version",
+                        short: None,
+                        value: Some("version"),
+                        description: "Minecraft version of the copy (mods are re-resolved)",
+                    },
+                ],
+                subcommands: &[],
+                hidden: false,
+            },
+            CommandSpec {
+                name: "import-instance",
+                aliases: &[],
+                args: &[ArgSpec {
+                    name: "path",
+                    required: false,
+                    many: false,
+                }],
+                description: "create a profile from a Prism Launcher / MultiMC instance",
+                flags: &[],
+                subcommands: &[],
+                hidden: false,
+            },
+        ],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "update",
+        aliases: &["upgrade"],
+        args: &[ArgSpec {
+            name: "mod",
+            required: false,
+            many: true,
+        }],
+        description: "show changelogs and update all mods (or only the named ones)",
+        flags: &[FlagSpec {
+            name: "--exclude",
+            short: None,
+            value: Some("mod"),
+            description: "skip a mod for this run without locking it (can be repeated)",
+        }],
+        subcommands: &[],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "changelog",
+        aliases: &[],
+        args: &[ArgSpec {
+            name: "mod",
+            required: false,
+            many: false,
+        }],
+        description: "show changelogs of pending updates",
+        flags: &[],
+        subcommands: &[],
+        hidden: false,
+    },
+    simple("outdated", "list available updates without applying them"),
+    CommandSpec {
+        name: "sync",
+        aliases: &[],
+        args: &[],
+        description: "make the mods folder match minefetch.toml",
+        flags: &[
+            FlagSpec {
+                name: "--manifest",
+                short: None,
+                value: Some("file"),
+                description: "use this manifest instead of the nearest minefetch.toml",
+            },
+            FlagSpec {
+                name: "--side",
+                short: None,
+                value: Some("side"),
+                description: "install only mods for this side (client or server)",
+            },
+            FlagSpec {
+                name: "--dry-run",
+                short: None,
+                value: None,
+                description: "only show what would change",
+            },
+        ],
+        subcommands: &[],
+        hidden: false,
+    },
+    simple("list", "list installed mods"),
+    CommandSpec {
+        name: "install",
+        aliases: &[],
+        args: &[],
+        description: "install exactly the files from minefetch.lock",
+        flags: &[
+            FlagSpec {
+                name: "--locked",
+                short: None,
+                value: None,
+                description: "use the lockfile (required)",
+            },
+            FlagSpec {
+                name: "--lockfile",
+                short: None,
+                value: Some("file"),
+                description: "use this lockfile instead of the nearest minefetch.lock",
+            },
+        ],
+        subcommands: &[],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "loader",
+        aliases: &[],
+        args: &[],
+        description: "manage the server loader",
+        flags: &[],
+        subcommands: &[
+            CommandSpec {
+                name: "install",
+                aliases: &[],
+                args: &[],
+                description: "download the server loader for the profile",
+                flags: &[FlagSpec {
+                    name: "--version",
+                    short: None,
+                    value: Some("version"),
+                    description: "this loader version instead of the newest stable one",
+                }],
+                subcommands: &[],
+                hidden: false,
+            },
+            simple("update", "download a newer server loader if there's one"),
+        ],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "lock",
+        aliases: &[],
+        args: &[],
+        description: "manage locks",
+        flags: &[],
+        subcommands: &[
+            simple("add", "add a lock which stops selected mod from updating"),
+            simple("remove", "delete a lock"),
+            simple("list", "list locks"),
+            CommandSpec {
+                name: "generate",
+                aliases: &[],
+                args: &[],
+                description: "write minefetch.lock with the exact installed files",
+                flags: &[FlagSpec {
+                    name: "--output",
+                    short: None,
+                    value: Some("file"),
+                    description: "write to this file instead of ./minefetch.lock",
+                }],
+                subcommands: &[],
+                hidden: false,
+            },
+        ],
+        hidden: false,
+    },
+    simple("edit", "install another version of an installed mod"),
+    simple("version", "display MineFetch version"),
+    CommandSpec {
+        name: "help",
+        aliases: &[],
+        args: &[ArgSpec {
+            name: "command",
+            required: false,
+            many: true,
+        }],
+        description: "show help for a command",
+        flags: &[],
+        subcommands: &[],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "completions",
+        aliases: &[],
+        args: &[ArgSpec {
+            name: "shell",
+            required: true,
+            many: false,
+        }],
+        description: "print the completion script for bash, zsh or fish",
+        flags: &[],
+        subcommands: &[],
+        hidden: false,
+    },
+    CommandSpec {
+        name: "__complete",
+        aliases: &[],
+        args: &[ArgSpec {
+            name: "word",
+            required: false,
+            many: true,
+        }],
+        description: "print candidates for the last word",
+        flags: &[],
+        subcommands: &[],
+        hidden: true,
+    },
+    CommandSpec {
+        name: "debug",
+        aliases: &[],
+        args: &[],
+        description: "print the user agent",
+        flags: &[],
+        subcommands: &[],
+        hidden: true,
+    },
+];
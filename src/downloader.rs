@@ -9,89 +9,428 @@
 
 // Internal modules
 use crate::api::Anymod;
-use crate::consts::USER_AGENT;
+use crate::consts::{BACKOFF_BASE_MS, DEFAULT_JOBS, MAX_RETRIES};
+use crate::error::MineFetchError;
+use crate::http::send;
+use crate::mfio::MFText;
 use crate::structs::WorkingProfile;
+use crate::utils::calculate_sha1;
 
 // Standard imports
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::result::Result;
 use std::sync::Arc;
+use std::time::Duration;
 
 // External crates
 use futures::future::join_all;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::Client;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_TYPE, RANGE};
 use tokio::fs::create_dir_all;
 use tokio::io::AsyncWriteExt;
-use tokio::task::JoinHandle;
+use tokio::sync::Semaphore;
+use tokio::task::{JoinHandle, spawn_blocking};
+use tokio::time::sleep;
 
-/// Downloads a single file
+/// The result of one download (with the hash of the file)
+enum Outcome {
+    Downloaded(String),
+    Skipped(String),
+    Failed(String, MineFetchError),
+}
+
+/// What a batch of downloads has done
+pub struct Downloads {
+    /// Hashes of the files that are in the mods' folder now
+    pub done: HashSet<String>,
+
+    /// The first failure, so the exit code matches what went wrong
+    pub error: Option<MineFetchError>,
+}
+
+impl Downloads {
+    /// Turns the first failure into an error
+    pub fn into_result(self) -> Result<(), MineFetchError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Downloads a single mod into the mods' folder
 pub async fn download_mod(
     anymod: &Anymod,
     working_profile: &WorkingProfile,
     bar: ProgressBar,
-) -> Result<(), Box<dyn std::error::Error>> {
+    total_bar: ProgressBar,
+) -> Result<(), MineFetchError> {
+    download_into(
+        anymod,
+        &working_profile.client,
+        Path::new(&working_profile.profile.modsfolder),
+        bar,
+        total_bar,
+    )
+    .await
+}
+
+/// Downloads a single file into the directory.
+/// The data goes into a .part file first, so an interrupted
+/// download can be resumed later with a Range request (only if the hash is known)
+pub async fn download_into(
+    anymod: &Anymod,
+    client: &Client,
+    dir: &Path,
+    bar: ProgressBar,
+    total_bar: ProgressBar,
+) -> Result<(), MineFetchError> {
     // Create a destination directory if it doesn't exist
-    create_dir_all(&working_profile.profile.modsfolder).await?;
+    create_dir_all(dir)
+        .await
+        .map_err(|error| MineFetchError::io(dir, error))?;
 
     // Create a file path
-    let path = Path::new(&working_profile.profile.modsfolder).join(&anymod.filename);
+    let path = dir.join(&anymod.filename);
+    let part = part_path(&path);
+
+    // Without a hash a leftover from another version can't be told apart, so don't resume it
+    if anymod.hash.is_empty() {
+        remove_part(&part).await?;
+    }
+
+    // How many bytes of this file are counted in the overall bar
+    let mut counted: u64 = 0;
+    let mut attempt: u32 = 0;
+
+    loop {
+        let result = match fetch_part(anymod, client, &part, &bar, &total_bar, &mut counted).await {
+            Ok(_) => verify_part(anymod, &part).await,
+            Err(error) => Err(error),
+        };
+
+        let error = match result {
+            Ok(_) => break,
+            Err(error) => error,
+        };
+
+        if attempt >= MAX_RETRIES || !worth_retrying(&error) {
+            bar.abandon();
+            return Err(error);
+        }
+
+        attempt += 1;
+        sleep(Duration::from_millis(BACKOFF_BASE_MS * 2u64.pow(attempt))).await;
+    }
+
+    // The file is complete, give it the real name
+    tokio::fs::rename(&part, &path)
+        .await
+        .map_err(|error| MineFetchError::io(&path, error))?;
+
+    bar.finish();
+
+    // Success
+    Ok(())
+}
+
+/// Downloads the rest of the file into the .part file
+async fn fetch_part(
+    anymod: &Anymod,
+    client: &Client,
+    part: &Path,
+    bar: &ProgressBar,
+    total_bar: &ProgressBar,
+    counted: &mut u64,
+) -> Result<(), MineFetchError> {
+    // How much has already been downloaded
+    let mut offset = match tokio::fs::metadata(part).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    };
+
+    // Don't ask for more if the file already has the expected size
+    if anymod.size.is_some_and(|size| offset >= size) {
+        return Ok(());
+    }
 
-    // Send the download request
-    let mut response = working_profile
-        .client
-        .get(&anymod.url)
-        .header("User-Agent", USER_AGENT)
-        .send()
-        .await?;
+    // Ask only for the missing part
+    let mut request = client.get(&anymod.url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    }
+
+    let mut response = match send(request).await {
+        Ok(response) => response,
 
-    let total = response.content_length().ok_or("But nobody came")?;
+        // The .part file is bigger than the file on the server
+        Err(MineFetchError::Status { status: 416, .. }) => {
+            remove_part(part).await?;
+            return Err(corrupted(anymod));
+        }
 
-    bar.set_length(total);
+        Err(error) => return Err(error),
+    };
 
-    // Create a file
-    let mut file = tokio::fs::File::create(path).await?;
+    // An error page instead of the file can't be fixed by retrying
+    if let Some(content_type) = response.headers().get(CONTENT_TYPE)
+        && content_type
+            .to_str()
+            .is_ok_and(|content_type| content_type.starts_with("text/html"))
+    {
+        return Err(MineFetchError::Corrupted(format!(
+            "{} is a web page, not a file",
+            anymod.url
+        )));
+    }
+
+    // The server may ignore the Range header and send the whole file
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        offset = 0;
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(offset > 0)
+        .write(true)
+        .truncate(offset == 0)
+        .open(part)
+        .await
+        .map_err(|error| MineFetchError::io(part, error))?;
+
+    // Forget what previous attempts have counted
+    total_bar.dec(*counted);
+    total_bar.inc(offset);
+    *counted = offset;
+
+    // Use the size from the response, then from Modrinth, otherwise show a spinner
+    match response
+        .content_length()
+        .map(|length| offset + length)
+        .or(anymod.size)
+    {
+        Some(length) => {
+            bar.set_length(length);
+
+            // The overall bar doesn't know the size of mods from old caches
+            if anymod.size.is_none() && offset == 0 {
+                total_bar.inc_length(length);
+            }
+        }
+        None => {
+            bar.set_style(
+                ProgressStyle::with_template("{wide_msg} {spinner} {bytes}")
+                    .expect("valid template"),
+            );
+            bar.enable_steady_tick(Duration::from_millis(100));
+        }
+    }
+
+    bar.set_position(offset);
 
     // Write into file gradually
     while let Some(chunk) = response.chunk().await? {
-        file.write(&chunk).await?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|error| MineFetchError::io(part, error))?;
         bar.inc(chunk.len() as u64);
+        total_bar.inc(chunk.len() as u64);
+        *counted += chunk.len() as u64;
     }
 
-    bar.finish();
+    file.flush()
+        .await
+        .map_err(|error| MineFetchError::io(part, error))?;
 
-    // Success
     Ok(())
 }
 
-/// Downloads multiple files
+/// Checks the size and the hash of the downloaded .part file
+async fn verify_part(anymod: &Anymod, part: &Path) -> Result<(), MineFetchError> {
+    let length = tokio::fs::metadata(part)
+        .await
+        .map_err(|error| MineFetchError::io(part, error))?
+        .len();
+
+    let size_matches = anymod.size.is_none_or(|size| size == length);
+
+    let path = part.to_path_buf();
+    let hash = spawn_blocking(move || calculate_sha1(&path))
+        .await?
+        .map_err(|error| MineFetchError::io(part, error))?;
+
+    // Some files have no known hash (Fabric's server launcher), only their size is checked
+    let hash_matches = anymod.hash.is_empty() || hash == anymod.hash;
+
+    // A broken file can't be resumed, so start again
+    if !size_matches || !hash_matches {
+        remove_part(part).await?;
+        return Err(corrupted(anymod));
+    }
+
+    Ok(())
+}
+
+/// Path of the unfinished file
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Removes the .part file
+async fn remove_part(part: &Path) -> Result<(), MineFetchError> {
+    match tokio::fs::remove_file(part).await {
+        Ok(_) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(MineFetchError::io(part, error)),
+    }
+}
+
+/// The error for a file that doesn't match its size or hash
+fn corrupted(anymod: &Anymod) -> MineFetchError {
+    MineFetchError::Corrupted(format!(
+        "{} from {} doesn't match its size or hash",
+        anymod.filename, anymod.url
+    ))
+}
+
+/// Broken connections and corrupted files are worth another try
+fn worth_retrying(error: &MineFetchError) -> bool {
+    matches!(
+        error,
+        MineFetchError::Network { .. } | MineFetchError::Corrupted(_)
+    )
+}
+
+/// Checks if the same file is already in the mods' folder
+async fn already_downloaded(anymod: &Anymod, working_profile: &WorkingProfile) -> bool {
+    let path = Path::new(&working_profile.profile.modsfolder).join(&anymod.filename);
+
+    if !path.is_file() {
+        return false;
+    }
+
+    match spawn_blocking(move || calculate_sha1(&path)).await {
+        Ok(Ok(hash)) => hash == anymod.hash,
+        _ => false,
+    }
+}
+
+/// Downloads multiple files, fails if any of them failed
 pub async fn download_multiple_mods(
     files: Vec<Anymod>,
     working_profile: Arc<WorkingProfile>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut tasks: Vec<JoinHandle<Result<(), ()>>> = Vec::with_capacity(files.len());
+) -> Result<(), MineFetchError> {
+    download_mods_reported(files, working_profile)
+        .await?
+        .into_result()
+}
+
+/// Downloads multiple files and tells which of them are in place.
+/// Replacing mods needs it to remove only the old files that have a new one
+pub async fn download_mods_reported(
+    files: Vec<Anymod>,
+    working_profile: Arc<WorkingProfile>,
+) -> Result<Downloads, MineFetchError> {
+    let mut tasks: Vec<JoinHandle<Outcome>> = Vec::with_capacity(files.len());
+
+    // Limit the amount of simultaneous downloads
+    let jobs = working_profile.settings.jobs.unwrap_or(DEFAULT_JOBS).max(1);
+    let semaphore = Arc::new(Semaphore::new(jobs));
 
     let multibar = Arc::new(MultiProgress::new());
     let style =
         ProgressStyle::with_template("{wide_msg} {bar:50} {percent}%").expect("valid template");
 
+    // The overall bar is always above the per-file bars
+    let total_bar = multibar.add(ProgressBar::new(
+        files.iter().filter_map(|file| file.size).sum(),
+    ));
+    total_bar.set_style(
+        ProgressStyle::with_template(
+            "{wide_msg} {bar:50} {bytes}/{total_bytes} {bytes_per_sec} ETA {eta}",
+        )
+        .expect("valid template"),
+    );
+    total_bar.set_message(format!("Total ({} mods)", files.len()));
+
     for file in files {
         let wp = working_profile.clone();
         let mb = multibar.clone();
         let st = style.clone();
+        let tb = total_bar.clone();
+        let sem = semaphore.clone();
         tasks.push(tokio::spawn(async move {
+            let name = file.title.clone().unwrap_or(file.filename.clone());
+
+            // Wait for a free slot
+            let _permit = match sem.acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => {
+                    return Outcome::Failed(name, MineFetchError::Cancelled);
+                }
+            };
+
+            if already_downloaded(&file, &wp).await {
+                tb.dec_length(file.size.unwrap_or_default());
+                return Outcome::Skipped(file.hash);
+            }
+
             let bar = mb.add(ProgressBar::new(0));
             bar.set_style(st);
-            bar.set_message(file.clone().title.unwrap_or(file.filename.clone()));
-            match download_mod(&file, &wp, bar).await {
-                Ok(_) => {}
-                Err(e) => eprintln!(":err: {e}"),
+            bar.set_message(name.clone());
+            match download_mod(&file, &wp, bar, tb).await {
+                Ok(_) => Outcome::Downloaded(file.hash),
+                Err(e) => Outcome::Failed(name, e),
             }
-            Ok(())
         }));
     }
 
-    join_all(tasks).await;
+    let mut downloaded: usize = 0;
+    let mut skipped: usize = 0;
+    let mut failed: Vec<(String, MineFetchError)> = Vec::new();
+    let mut done: HashSet<String> = HashSet::new();
 
-    // Success
-    Ok(())
+    for result in join_all(tasks).await {
+        match result? {
+            Outcome::Downloaded(hash) => {
+                downloaded += 1;
+                done.insert(hash);
+            }
+            Outcome::Skipped(hash) => {
+                skipped += 1;
+                done.insert(hash);
+            }
+            Outcome::Failed(name, error) => failed.push((name, error)),
+        }
+    }
+
+    total_bar.finish();
+
+    // Print the summary
+    println!(
+        ":out: Downloaded: {}{}{}, failed: {}{}{}, skipped: {}{}{}",
+        MFText::Bold,
+        downloaded,
+        MFText::Reset,
+        MFText::Bold,
+        failed.len(),
+        MFText::Reset,
+        MFText::Bold,
+        skipped,
+        MFText::Reset
+    );
+
+    for (name, error) in &failed {
+        eprintln!(":err: {name}: {error}");
+    }
+
+    Ok(Downloads {
+        done,
+        error: failed.into_iter().next().map(|(_, error)| error),
+    })
 }
@@ -0,0 +1,232 @@
+/*
+ _____
+| ____|_ __ _ __ ___  _ __ ___
+|  _| | '__| '__/ _ \| '__/ __|
+| |___| |  | | | (_) | |  \__ \
+|_____|_|  |_|  \___/|_|  |___/
+
+*/
+
+// Standard imports
+use std::fmt;
+use std::path::Path;
+
+/*
+    Exit codes. Every error category has its own code
+    so scripts can tell what went wrong without parsing
+    the message:
+
+     0  success (this includes "nothing to update")
+     2  invalid input (bad arguments, wrong selection, already installed)
+     3  network error (no connection, timeout, TLS)
+     4  the API answered with a non-successful HTTP status
+     5  a response or a local file couldn't be parsed
+     6  the requested mod / version / profile doesn't exist
+     7  the mod is locked
+     8  config problem (no config, no active profile)
+     9  filesystem error
+    10  cancelled by the user
+    11  a downloaded file doesn't match its size or hash
+   100  'outdated' found updates (not an error, meant for cron jobs)
+*/
+
+pub const EXIT_INVALID: u8 = 2;
+pub const EXIT_NETWORK: u8 = 3;
+pub const EXIT_STATUS: u8 = 4;
+pub const EXIT_PARSE: u8 = 5;
+pub const EXIT_NOT_FOUND: u8 = 6;
+pub const EXIT_LOCKED: u8 = 7;
+pub const EXIT_CONFIG: u8 = 8;
+pub const EXIT_IO: u8 = 9;
+pub const EXIT_CANCELLED: u8 = 10;
+pub const EXIT_CORRUPTED: u8 = 11;
+pub const EXIT_UPDATES_AVAILABLE: u8 = 100;
+
+/// All errors that MineFetch can return
+#[derive(Debug)]
+pub enum MineFetchError {
+    /// Wrong user input
+    Invalid(String),
+
+    /// The request couldn't be sent or the connection broke
+    Network { url: String, message: String },
+
+    /// The server answered with a non-successful status
+    Status {
+        url: String,
+        status: u16,
+        body: String,
+    },
+
+    /// A response or a file couldn't be parsed
+    Parse { what: String, message: String },
+
+    /// Something that was asked for doesn't exist
+    NotFound(String),
+
+    /// The mod is locked (contains the mod's name or slug)
+    Locked(String),
+
+    /// Config is missing or broken
+    Config(String),
+
+    /// Filesystem error (with the path if it's known)
+    Io {
+        path: Option<String>,
+        message: String,
+    },
+
+    /// The user cancelled the operation
+    Cancelled,
+
+    /// A downloaded file isn't what was expected (wrong size or hash)
+    Corrupted(String),
+}
+
+impl MineFetchError {
+    /// Returns the process exit code for this error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            MineFetchError::Invalid(_) => EXIT_INVALID,
+            MineFetchError::Network { .. } => EXIT_NETWORK,
+            MineFetchError::Status { .. } => EXIT_STATUS,
+            MineFetchError::Parse { .. } => EXIT_PARSE,
+            MineFetchError::NotFound(_) => EXIT_NOT_FOUND,
+            MineFetchError::Locked(_) => EXIT_LOCKED,
+            MineFetchError::Config(_) => EXIT_CONFIG,
+            MineFetchError::Io { .. } => EXIT_IO,
+            MineFetchError::Cancelled => EXIT_CANCELLED,
+            MineFetchError::Corrupted(_) => EXIT_CORRUPTED,
+        }
+    }
+
+    /// Creates an Io error that remembers the path
+    pub fn io(path: impl AsRef<Path>, error: std::io::Error) -> Self {
+        MineFetchError::Io {
+            path: Some(path.as_ref().display().to_string()),
+            message: error.to_string(),
+        }
+    }
+
+    /// Creates a Parse error with a description of what was parsed
+    pub fn parse(what: impl Into<String>, error: impl fmt::Display) -> Self {
+        MineFetchError::Parse {
+            what: what.into(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Display for MineFetchError
+impl fmt::Display for MineFetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MineFetchError::Invalid(message)
+            | MineFetchError::NotFound(message)
+            | MineFetchError::Config(message)
+            | MineFetchError::Corrupted(message) => write!(f, "{message}"),
+            MineFetchError::Network { url, message } if url.is_empty() => {
+                write!(f, "Network error: {message}")
+            }
+            MineFetchError::Network { url, message } => {
+                write!(f, "Network error while requesting {url}: {message}")
+            }
+            MineFetchError::Status { url, status, body } if body.is_empty() => {
+                write!(f, "{url} returned HTTP {status}")
+            }
+            MineFetchError::Status { url, status, body } => {
+                write!(f, "{url} returned HTTP {status}: {body}")
+            }
+            MineFetchError::Parse { what, message } => {
+                write!(f, "Couldn't parse {what}: {message}")
+            }
+            MineFetchError::Locked(name) => write!(f, "The mod {name} is locked"),
+            MineFetchError::Io {
+                path: Some(path),
+                message,
+            } => write!(f, "{path}: {message}"),
+            MineFetchError::Io {
+                path: None,
+                message,
+            } => write!(f, "{message}"),
+            MineFetchError::Cancelled => write!(f, "Cancelled by user"),
+        }
+    }
+}
+
+impl std::error::Error for MineFetchError {}
+
+/// Sorts reqwest errors into network, status and parse errors
+impl From<reqwest::Error> for MineFetchError {
+    fn from(error: reqwest::Error) -> Self {
+        let url = error.url().map(|url| url.to_string()).unwrap_or_default();
+
+        if let Some(status) = error.status() {
+            return MineFetchError::Status {
+                url,
+                status: status.as_u16(),
+                body: String::new(),
+            };
+        }
+
+        if error.is_decode() {
+            return MineFetchError::parse(format!("the response from {url}"), error);
+        }
+
+        // The lowest error in the chain usually says what really happened
+        let mut cause: &dyn std::error::Error = &error;
+        while let Some(source) = cause.source() {
+            cause = source;
+        }
+        let root = cause.to_string();
+
+        let message = match error.without_url().to_string() {
+            top if top == root => top,
+            top => format!("{top}: {root}"),
+        };
+
+        MineFetchError::Network { url, message }
+    }
+}
+
+impl From<std::io::Error> for MineFetchError {
+    fn from(error: std::io::Error) -> Self {
+        MineFetchError::Io {
+            path: None,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for MineFetchError {
+    fn from(error: serde_json::Error) -> Self {
+        MineFetchError::parse("JSON", error)
+    }
+}
+
+impl From<toml::de::Error> for MineFetchError {
+    fn from(error: toml::de::Error) -> Self {
+        MineFetchError::parse("TOML", error)
+    }
+}
+
+impl From<toml::ser::Error> for MineFetchError {
+    fn from(error: toml::ser::Error) -> Self {
+        MineFetchError::parse("TOML", error)
+    }
+}
+
+impl From<url::ParseError> for MineFetchError {
+    fn from(error: url::ParseError) -> Self {
+        MineFetchError::parse("URL", error)
+    }
+}
+
+impl From<tokio::task::JoinError> for MineFetchError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        MineFetchError::Io {
+            path: None,
+            message: format!("Task failed: {error}"),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ _____ _ _      _            _
+|  ___(_) | ___| | ___   ___| | __
+| |_  | | |/ _ \ |/ _ \ / __| |/ /
+|  _| | | |  __/ | (_) | (__|   <
+|_|   |_|_|\___|_|\___/ \___|_|\_\
+
+*/
+
+// Standard imports
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+// External crates
+use tokio::task::spawn_blocking;
+
+// Internal modules
+use crate::error::MineFetchError;
+use crate::structs::Profile;
+use crate::utils::get_confpath;
+
+/// The lock file inside every mods' folder
+const PROFILE_LOCK_NAME: &str = ".minefetch-profile.lock";
+
+/// How a lock is taken
+#[derive(Clone, Copy)]
+pub enum LockMode {
+    /// For commands that only read (many readers at once)
+    Shared,
+
+    /// For commands that change files (only one at a time)
+    Exclusive,
+}
+
+/// An advisory lock, it's released when dropped
+pub struct FileLock {
+    // Closing the file releases the lock
+    _file: Option<File>,
+}
+
+/// Locks the profile's mods folder (cache.toml, locks.toml and the jars)
+pub async fn lock_profile(profile: &Profile, mode: LockMode) -> Result<FileLock, MineFetchError> {
+    let modsfolder = Path::new(&profile.modsfolder);
+
+    // Don't bring back a removed or mistyped folder just to lock it
+    if !tokio::fs::metadata(modsfolder)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        return Err(MineFetchError::NotFound(format!(
+            "The mods folder {} of the profile {} doesn't exist, create it or change it with 'minefetch profile edit --dir'",
+            profile.modsfolder, profile.name
+        )));
+    }
+
+    lock_file(modsfolder.join(PROFILE_LOCK_NAME), mode).await
+}
+
+/// Locks config.toml for a read-modify-write
+pub async fn lock_config() -> Result<FileLock, MineFetchError> {
+    let path = config_lock_path().await?;
+
+    // The config folder may not exist before the first profile is created
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|error| MineFetchError::io(parent, error))?;
+    }
+
+    lock_file(path, LockMode::Exclusive).await
+}
+
+/// Locks config.toml only if nobody holds the lock (this process included).
+/// For writes that can be skipped, like saving a migrated config
+pub async fn try_lock_config() -> Option<FileLock> {
+    let path = config_lock_path().await.ok()?;
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .ok()?;
+
+    spawn_blocking(move || match try_flock(&file, LockMode::Exclusive, false) {
+        Ok(true) => Some(FileLock { _file: Some(file) }),
+        _ => None,
+    })
+    .await
+    .ok()?
+}
+
+/// config.toml -> config.toml.lock
+async fn config_lock_path() -> Result<PathBuf, MineFetchError> {
+    let mut lock_path = get_confpath().await?.into_os_string();
+    lock_path.push(".lock");
+
+    Ok(PathBuf::from(lock_path))
+}
+
+/// Opens (or creates) the lock file in an existing folder and waits until the lock is taken
+async fn lock_file(path: PathBuf, mode: LockMode) -> Result<FileLock, MineFetchError> {
+    let file = match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+    {
+        Ok(file) => file,
+
+        // A read-only folder can't be changed anyway, don't block reading it
+        Err(error) => {
+            if let LockMode::Exclusive = mode {
+                eprintln!(":wrn: Can't lock {}: {}", path.display(), error);
+            }
+            return Ok(FileLock { _file: None });
+        }
+    };
+
+    let file = spawn_blocking(move || -> std::io::Result<File> {
+        // Try first, so the user knows why nothing happens
+        if !try_flock(&file, mode, false)? {
+            eprintln!(":out: Waiting for another MineFetch process to finish...");
+            try_flock(&file, mode, true)?;
+        }
+
+        Ok(file)
+    })
+    .await?
+    .map_err(|error| MineFetchError::io(&path, error))?;
+
+    Ok(FileLock { _file: Some(file) })
+}
+
+/// Calls flock(2). Returns false if the lock is busy and 'wait' is false
+#[cfg(unix)]
+fn try_flock(file: &File, mode: LockMode, wait: bool) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut operation = match mode {
+        LockMode::Shared => libc::LOCK_SH,
+        LockMode::Exclusive => libc::LOCK_EX,
+    };
+
+    if !wait {
+        operation |= libc::LOCK_NB;
+    }
+
+    loop {
+        // SAFETY: the descriptor belongs to 'file', which outlives this call
+        let result = unsafe { libc::flock(file.as_raw_fd(), operation) };
+
+        if result == 0 {
+            return Ok(true);
+        }
+
+        let error = std::io::Error::last_os_error();
+
+        match error.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            Some(libc::EWOULDBLOCK) if !wait => return Ok(false),
+            _ => return Err(error),
+        }
+    }
+}
+
+/// There's no flock(2) here, so there's no locking either
+#[cfg(not(unix))]
+fn try_flock(_file: &File, _mode: LockMode, _wait: bool) -> std::io::Result<bool> {
+    Ok(true)
+}
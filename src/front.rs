@@ -1,39 +1,68 @@
 // Standard libraries
-use std::error::Error;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 // Internal modules
 use crate::api::{
-    Anymod, edit_mod, get_dependencies_recursive, get_latest_version, replace_mods, search_mods,
-    upgrade_mods,
+    Anymod, edit_mod, fetch_versions, find_updates, get_changelogs, get_dependencies_recursive,
+    get_latest_version, get_members, get_project, get_projects_name, replace_mods, search_mods,
+    upgrade_mods, version_to_anymod,
 };
 use crate::cache::{list_mods_cached, validate_cache};
-use crate::downloader::download_multiple_mods;
+use crate::consts::{INFO_VERSIONS, LOADERS};
+use crate::downloader::{download_mods_reported, download_multiple_mods};
+use crate::error::MineFetchError;
+use crate::filelock::{LockMode, lock_config, lock_profile};
+use crate::instance::{find_instances, read_instance};
+use crate::loader::{download_release, find_release};
+use crate::lockfile::{
+    LOCKFILE_NAME, LockedMod, Lockfile, find_lockfile, read_lockfile, write_lockfile,
+};
+use crate::manifest::{ManifestMod, find_manifest, read_manifest};
+use crate::marker::MARKER_NAME;
 use crate::mfio::{MFText, ainput, parse_to_int, select};
-use crate::profile::{add_lock, build_working_profile, list_locks, read_full_config, remove_lock};
-use crate::structs::{Config, Dependency, Profile};
-use crate::utils::{generate_hash, get_confdir, get_confpath};
+use crate::profile::{
+    add_lock, build_working_profile, get_locks, list_locks, read_full_config, remove_lock,
+    select_profile, write_config,
+};
+use crate::structs::{
+    Dependency, Options, OutdatedMod, Profile, ProfileClone, ProfileEdit, SearchQuery, SyncArgs,
+    Version, WorkingProfile,
+};
+use crate::utils::{
+    calculate_sha1, generate_hash, get_confpath, get_hashed_jars, get_hashes, remove_mods_by_hash,
+};
+
+// External crates
+use tokio::task::spawn_blocking;
+
+pub async fn add_mod(modname: &str, options: &Options) -> Result<(), MineFetchError> {
+    // This command can't work without Modrinth
+    require_online(options, "add")?;
 
-pub async fn add_mod(modname: &str) -> Result<(), Box<dyn Error>> {
     // Print text
     println!(":out: Adding a mod...");
 
     // Create a working profile
-    let working_profile = build_working_profile().await?;
+    let working_profile = build_working_profile(options).await?;
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
 
     // Get the latest version
-    let mod_version = get_latest_version(&modname.to_string(), &working_profile).await?;
+    let mod_version = get_latest_version(modname, &working_profile).await?;
 
     let mod_list = list_mods_cached(&working_profile).await?;
 
     for anymod in &mod_list {
         if anymod.project_id == mod_version.project_id {
-            return Err(format!(
+            return Err(MineFetchError::Invalid(format!(
                 "The mod {} is already installed",
-                mod_version.title.as_ref().ok_or("No title")?
-            )
-            .into());
+                mod_version.title.as_deref().unwrap_or(modname)
+            )));
         }
     }
 
@@ -42,7 +71,7 @@ pub async fn add_mod(modname: &str) -> Result<(), Box<dyn Error>> {
 
     // Check for existing dependencies
     if let Some(dependencies) = mod_version.depends {
-        let deps = get_dependencies_recursive(&dependencies, &working_profile).await?;
+        let deps = get_dependencies_recursive(&dependencies, &mod_list, &working_profile).await?;
 
         for (dep, dep_type) in deps {
             let mut installed: bool = false;
@@ -80,31 +109,48 @@ pub async fn add_mod(modname: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub async fn search(args: Vec<String>) -> Result<(), Box<dyn Error>> {
-    // Join all the strings to form a query
-    let query = args[2..].join(" ");
+pub async fn search(query: SearchQuery, options: &Options) -> Result<(), MineFetchError> {
+    // This command can't work without Modrinth
+    require_online(options, "search")?;
 
     // Create a working profile
-    let working_profile = build_working_profile().await?;
+    let working_profile = build_working_profile(options).await?;
 
-    /*
-    Get the current mods' list to compare with
-    versions that user will try to install. It's needed to
-    ensure that there won't be any duplicates of mods
-    */
-
-    let mod_list = list_mods_cached(&working_profile).await.unwrap_or_default();
+    // Installed mods are marked in the hits (only read, the prompt doesn't block writers)
+    let mod_list = {
+        let _lock = lock_profile(&working_profile.profile, LockMode::Shared).await?;
+        list_mods_cached(&working_profile).await.unwrap_or_default()
+    };
 
-    /*
-        search_mods() prompts a user to select mods in menu.
-        So, 'files' contains a list of mods to install.
-    */
+    // search_mods() only returns the hits, the user picks them below
     let hits = search_mods(&query, &working_profile).await?;
 
     // Print all hits
     for number in (0..hits.len()).rev() {
         if let Some(hit) = hits.get(number) {
-            println!("[{}] {}", number + 1, hit.title);
+            let installed = mod_list
+                .iter()
+                .any(|anymod| anymod.project_id == hit.project_id);
+
+            println!(
+                "[{}{}{}] {}{}{} by {}{}",
+                MFText::Bold,
+                number + 1,
+                MFText::Reset,
+                MFText::Bold,
+                hit.title,
+                MFText::Reset,
+                hit.author,
+                if installed { " [installed]" } else { "" }
+            );
+            println!("      {}", hit.description);
+            println!(
+                "      {} downloads | updated {} | client: {}, server: {}",
+                short_number(hit.downloads),
+                hit.date_modified.get(..10).unwrap_or(&hit.date_modified),
+                hit.client_side,
+                hit.server_side
+            );
         }
     }
 
@@ -114,6 +160,16 @@ pub async fn search(args: Vec<String>) -> Result<(), Box<dyn Error>> {
     // Create a selected number list
     let numbers = parse_to_int(selected_string)?;
 
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
+
+    /*
+    Read the mods' list again, another process could install
+    something while the user was choosing. It's needed to
+    ensure that there won't be any duplicates of mods
+    */
+    let mod_list = list_mods_cached(&working_profile).await.unwrap_or_default();
+
     let mut required: Vec<Anymod> = Vec::new();
     let mut merged_depends: Vec<Dependency> = Vec::new();
 
@@ -147,14 +203,17 @@ pub async fn search(args: Vec<String>) -> Result<(), Box<dyn Error>> {
                     required.push(version);
                 }
             }
-            None => return Err("The number is out of range".into()),
+            None => {
+                return Err(MineFetchError::Invalid("The number is out of range".into()));
+            }
         };
     }
-    
+
     let mut optional: Vec<Anymod> = vec![];
 
     if !merged_depends.is_empty() {
-        let depends = get_dependencies_recursive(&merged_depends, &working_profile).await?;
+        let depends =
+            get_dependencies_recursive(&merged_depends, &mod_list, &working_profile).await?;
         for (dep, dep_type) in depends {
             let mut installed: bool = false;
 
@@ -177,7 +236,7 @@ pub async fn search(args: Vec<String>) -> Result<(), Box<dyn Error>> {
 
     // Download 'files'
     download_multiple_mods(required, Arc::new(working_profile.clone())).await?;
-    
+
     if !optional.is_empty() {
         println!("Optional mods:");
 
@@ -192,14 +251,148 @@ pub async fn search(args: Vec<String>) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Shows details about a mod before installing it
+pub async fn info(modname: &str, options: &Options) -> Result<(), MineFetchError> {
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Writers wait until this is done
+    let _lock = lock_profile(&working_profile.profile, LockMode::Shared).await?;
+
+    let project = get_project(modname, &working_profile).await?;
+    let members = get_members(&project.id, &working_profile).await?;
+    let versions = fetch_versions(&project.id, &working_profile)
+        .await
+        .unwrap_or_default();
+    let mod_list = list_mods_cached(&working_profile).await.unwrap_or_default();
+
+    println!(
+        ":out: {}{}{} ({} / {})",
+        MFText::Bold,
+        project.title,
+        MFText::Reset,
+        project.slug,
+        project.id
+    );
+    println!("      {}", project.description);
+    println!(
+        "      License: {} ({})",
+        project.license.name, project.license.id
+    );
+    println!(
+        "      Authors: {}",
+        members
+            .iter()
+            .map(|member| format!("{} ({})", member.user.username, member.role))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+
+    for (name, link) in [
+        ("Source", &project.source_url),
+        ("Issues", &project.issues_url),
+        ("Wiki", &project.wiki_url),
+    ] {
+        if let Some(link) = link {
+            println!("      {name}: {link}");
+        }
+    }
+
+    println!("      Loaders: {}", project.loaders.join(", "));
+    println!("      Game versions: {}", project.game_versions.join(", "));
+
+    // Is it installed in the current profile?
+    match mod_list
+        .iter()
+        .find(|anymod| anymod.project_id == project.id)
+    {
+        Some(anymod) => println!(
+            "      Installed: {} ({})",
+            anymod.version_name, anymod.filename
+        ),
+        None => println!("      Installed: no"),
+    }
+
+    if versions.is_empty() {
+        println!(
+            ":out: There are no versions for {} {}",
+            working_profile.profile.loader, working_profile.profile.gameversion
+        );
+        return Ok(());
+    }
+
+    let versions: Vec<_> = versions.into_iter().take(INFO_VERSIONS).collect();
+
+    // Get the names of all dependencies with one request
+    let dependency_ids: Vec<&String> = versions
+        .iter()
+        .flat_map(|version| version.dependencies.iter().flatten())
+        .map(|dependency| &dependency.project_id)
+        .collect();
+
+    let titles: HashMap<String, String> = match dependency_ids.is_empty() {
+        true => HashMap::new(),
+        false => get_projects_name(&working_profile, dependency_ids)
+            .await?
+            .into_iter()
+            .map(|project| (project.id, project.title))
+            .collect(),
+    };
+
+    println!(
+        ":out: Latest versions for {} {}:",
+        working_profile.profile.loader, working_profile.profile.gameversion
+    );
+
+    for (number, version) in versions.iter().enumerate() {
+        println!(
+            "[{}{}{}] {}{}{} ({}, {})",
+            MFText::Bold,
+            number + 1,
+            MFText::Reset,
+            MFText::Bold,
+            version.name,
+            MFText::Reset,
+            version.version_type,
+            version
+                .date_published
+                .get(..10)
+                .unwrap_or(&version.date_published)
+        );
+
+        for dependency in version.dependencies.iter().flatten() {
+            println!(
+                "      {}: {}",
+                dependency.dependency_type,
+                titles
+                    .get(&dependency.project_id)
+                    .unwrap_or(&dependency.project_id)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats big numbers like 1.2K or 3.4M
+fn short_number(number: u64) -> String {
+    match number {
+        0..1_000 => number.to_string(),
+        1_000..1_000_000 => format!("{:.1}K", number as f64 / 1_000.0),
+        _ => format!("{:.1}M", number as f64 / 1_000_000.0),
+    }
+}
+
 /// Creates config file
-pub async fn create_profile() -> Result<(), Box<dyn Error>> {
+pub async fn create_profile() -> Result<(), MineFetchError> {
     // Get selected folder
     let modsfolder = {
         let buffer = ainput(":: Enter the path to mods directory: ").await?;
         let path = Path::new(&buffer);
         if !path.exists() {
-            return Err("There's no directory in this path".into());
+            return Err(MineFetchError::NotFound(format!(
+                "There's no directory in this path: {buffer}"
+            )));
         }
         buffer.trim().to_string()
     };
@@ -207,25 +400,20 @@ pub async fn create_profile() -> Result<(), Box<dyn Error>> {
     // Get minecraft version
     let gameversion = ainput(":out: Enter the Minecraft version: ").await?;
 
-    // A list of loaders
-    let loaders: Vec<(&str, &str)> = vec![
-        ("Quilt", "quilt"),
-        ("Fabric", "fabric"),
-        ("Forge", "forge"),
-        ("NeoForge", "neoforge"),
-    ];
-
     // Ask user to select a loader
-    let loader = select("Choose a loader", loaders).await?;
+    let loader = select("Choose a loader", LOADERS.to_vec()).await?;
 
     // Ask to enter the name of the profile
     let name = ainput(":out: What should this profile be called? ").await?;
 
+    // No other process may change the config meanwhile
+    let _lock = lock_config().await?;
+
     // Get a full config
-    let mut current_config = match read_full_config().await {
-        Ok(config) => config,
-        Err(_) => Config::default(),
-    };
+    let mut current_config = read_full_config().await.unwrap_or_default();
+
+    // --profile <name> has to find exactly one profile
+    ensure_unique_name(&current_config.profile, &name, None)?;
 
     // Create a new profile
     let new_profile = Profile {
@@ -235,6 +423,8 @@ pub async fn create_profile() -> Result<(), Box<dyn Error>> {
         gameversion,
         loader: loader.to_string(),
         hash: generate_hash().await?,
+        instance: None,
+        loader_version: None,
     };
 
     // Set every previous profile as inactive
@@ -245,39 +435,46 @@ pub async fn create_profile() -> Result<(), Box<dyn Error>> {
     // Push the new profile in the config
     current_config.profile.push(new_profile);
 
-    // Translate into toml string
-    let string_toml = toml::to_string(&current_config)?;
-
-    // Get config directory
-    let config_dir = get_confdir().await?;
-
-    // Get a config path
-    let config_path = get_confpath().await?;
-
-    // Create a config folder if it doesn't exist
-    tokio::fs::create_dir_all(config_dir).await?;
-
     // Write a config
-    tokio::fs::write(config_path, string_toml).await?;
+    write_config(&current_config).await?;
 
     // Success
     Ok(())
 }
 
+/// Fails if another profile (not the one with 'hash') already has this name
+fn ensure_unique_name(
+    profiles: &[Profile],
+    name: &str,
+    hash: Option<&str>,
+) -> Result<(), MineFetchError> {
+    if profiles
+        .iter()
+        .any(|profile| profile.name == name && Some(profile.hash.as_str()) != hash)
+    {
+        return Err(MineFetchError::Invalid(format!(
+            "There's already a profile named {name}, choose another name"
+        )));
+    }
+
+    Ok(())
+}
+
 /// Deletes one selected profile
-pub async fn delete_profile(all: u32) -> Result<(), Box<dyn Error>> {
-    // Get a mutable config
-    let mut config = match read_full_config().await {
-        Ok(config) => config,
-        Err(_) => {
-            return Err("There's no config yet, type minefetch profile create".into());
-        }
-    };
+pub async fn delete_profile(all: u32) -> Result<(), MineFetchError> {
+    // Get a config
+    let config = read_full_config().await?;
 
     let path = get_confpath().await?;
 
     if all == 1 {
-        tokio::fs::remove_file(path).await?;
+        let _lock = lock_config().await?;
+
+        tokio::fs::remove_file(&path)
+            .await
+            .map_err(|error| MineFetchError::io(&path, error))?;
+
+        return Ok(());
     }
 
     // Create a profile menu
@@ -289,39 +486,32 @@ pub async fn delete_profile(all: u32) -> Result<(), Box<dyn Error>> {
 
     // If there's no profiles
     if profiles.is_empty() {
-        return Err("There are no profiles yet".into());
+        return Err(MineFetchError::NotFound("There are no profiles yet".into()));
     };
 
     // Get a selected profile
     let selected_value = select("Which profile to delete?", profiles).await?;
 
+    // Read the config again under the lock, it could change while the menu was open
+    let _lock = lock_config().await?;
+    let mut config = read_full_config().await?;
+
     // Leave all profiles that don't have the same hash
     config
         .profile
         .retain(|profile| profile.hash != selected_value);
 
-    // Translate into toml string
-    let string_toml = toml::to_string(&config)?;
-
-    // Get a config path
-    let config_path = get_confpath().await?;
-
     // Write a config
-    tokio::fs::write(config_path, string_toml).await?;
+    write_config(&config).await?;
 
     // Success
     Ok(())
 }
 
 /// Switches profile to selected one
-pub async fn switch_profile() -> Result<(), Box<dyn Error>> {
-    // Get a mutable config
-    let mut config = match read_full_config().await {
-        Ok(config) => config,
-        Err(_) => {
-            return Err("There's no config yet, type minefetch profile create".into());
-        }
-    };
+pub async fn switch_profile() -> Result<(), MineFetchError> {
+    // Get a config
+    let config = read_full_config().await?;
 
     // Create a profile menu
     let profiles: Vec<(String, String)> = config
@@ -358,38 +548,130 @@ pub async fn switch_profile() -> Result<(), Box<dyn Error>> {
     // Get a selected profile hash
     let selected_hash = select("Which profile to switch to?", profiles).await?;
 
+    // Read the config again under the lock, it could change while the menu was open
+    let _lock = lock_config().await?;
+    let mut config = read_full_config().await?;
+
     // Set a selected profile to active and others to inactive
     for profile in config.profile.iter_mut() {
-        if profile.hash == *selected_hash {
-            profile.active = true
-        } else {
-            profile.active = false;
-        }
+        profile.active = profile.hash == *selected_hash;
     }
 
-    // Translate into toml string
-    let string_toml = toml::to_string(&config)?;
-
-    // Get a config path
-    let config_path = get_confpath().await?;
-
     // Write a config
-    tokio::fs::write(config_path, string_toml).await?;
+    write_config(&config).await?;
 
     // Success
     Ok(())
 }
 
-/// Lists all profiles
-pub async fn list_profiles() -> Result<(), Box<dyn Error>> {
-    // Get all profiles
-    let config = match read_full_config().await {
-        Ok(config) => config,
-        Err(_) => {
-            return Err("There's no config yet, type minefetch profile create".into());
+/// Imports a Prism Launcher / MultiMC instance as a profile
+/// (without a path it looks for instances in the launchers' default directories)
+pub async fn import_instance(path: Option<&str>) -> Result<(), MineFetchError> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let instances = find_instances().await?;
+
+            if instances.is_empty() {
+                return Err(MineFetchError::NotFound(
+                    "No Prism Launcher or MultiMC instances found, pass the path to one".into(),
+                ));
+            }
+
+            let menu: Vec<(String, PathBuf)> = instances
+                .into_iter()
+                .map(|path| (path.display().to_string(), path))
+                .collect();
+
+            select("Which instance to import?", menu).await?
+        }
+    };
+
+    // Absolute paths keep working from any directory
+    let path = tokio::fs::canonicalize(&path)
+        .await
+        .map_err(|error| MineFetchError::io(&path, error))?;
+
+    let instance = read_instance(&path).await?;
+
+    // Names have to be unique, ask for another one if the instance's is taken
+    let mut name = instance.name.clone();
+    let existing = read_full_config().await.unwrap_or_default();
+    while let Err(error) = ensure_unique_name(&existing.profile, &name, None) {
+        if !std::io::stdin().is_terminal() {
+            return Err(error);
         }
+        name = ainput(&format!(
+            ":out: There's already a profile named {name}, enter another name: "
+        ))
+        .await?;
+
+        // An empty answer gives up
+        if name.is_empty() {
+            return Err(MineFetchError::Cancelled);
+        }
+    }
+
+    // The launcher creates the mods folder only when it's needed
+    tokio::fs::create_dir_all(&instance.modsfolder)
+        .await
+        .map_err(|error| MineFetchError::io(&instance.modsfolder, error))?;
+
+    let _lock = lock_config().await?;
+    let mut config = read_full_config().await.unwrap_or_default();
+
+    let instance_path = path.display().to_string();
+
+    if let Some(profile) = config
+        .profile
+        .iter()
+        .find(|profile| profile.instance.as_ref() == Some(&instance_path))
+    {
+        return Err(MineFetchError::Invalid(format!(
+            "This instance is already imported as {}",
+            profile.name
+        )));
+    }
+
+    // Another process could take the name while the user was typing
+    ensure_unique_name(&config.profile, &name, None)?;
+
+    let new_profile = Profile {
+        active: true,
+        name,
+        modsfolder: instance.modsfolder.display().to_string(),
+        gameversion: instance.gameversion,
+        loader: instance.loader,
+        hash: generate_hash().await?,
+        instance: Some(instance_path),
+        loader_version: None,
     };
 
+    // The imported profile becomes the active one, like a created one
+    for profile in config.profile.iter_mut() {
+        profile.active = false;
+    }
+
+    println!(
+        ":out: Imported {}{}{} [{} {}] [{}]",
+        MFText::Bold,
+        new_profile.name,
+        MFText::Reset,
+        new_profile.loader,
+        new_profile.gameversion,
+        new_profile.modsfolder
+    );
+
+    config.profile.push(new_profile);
+
+    write_config(&config).await
+}
+
+/// Lists all profiles
+pub async fn list_profiles() -> Result<(), MineFetchError> {
+    // Get all profiles
+    let config = read_full_config().await?;
+
     // Print the profiles
     for profile in config.profile {
         // If it's active then add an asterisk
@@ -422,110 +704,1574 @@ pub async fn list_profiles() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub async fn upgrade() -> Result<(), Box<dyn Error>> {
-    // Create a working profile
-    let working_profile = build_working_profile().await?;
+/// Edits the selected (--profile) or active profile, with flags or interactively
+pub async fn edit_profile(mut edit: ProfileEdit, options: &Options) -> Result<(), MineFetchError> {
+    let config = read_full_config().await?;
 
-    // Returns a list of new files of mods to install
-    let (old_mods, new_mods) = upgrade_mods(&working_profile).await?;
+    // Find the profile to edit
+    let selected = select_profile(&config.profile, options).await?;
+    let old = config
+        .profile
+        .iter()
+        .find(|profile| profile.hash == selected.hash)
+        .cloned()
+        .ok_or_else(|| {
+            MineFetchError::Invalid(format!(
+                "The profile {} is defined in {}, edit that file instead",
+                selected.name, MARKER_NAME
+            ))
+        })?;
+
+    // Without flags ask for every field (empty input keeps the value)
+    if edit.is_empty() {
+        edit = ask_profile_edit(&old).await?;
+    }
 
-    // If empty then there're no mods to update
-    if new_mods.len() == 0 {
-        println!(":out: All mods are up to date!");
-        return Ok(());
+    if let Some(modsfolder) = &edit.modsfolder
+        && !Path::new(modsfolder).is_dir()
+    {
+        return Err(MineFetchError::NotFound(format!(
+            "There's no directory in this path: {modsfolder}"
+        )));
     }
 
-    // Download 'files'
-    replace_mods(
-        old_mods.iter().map(|hash| hash).collect(),
-        new_mods,
-        &working_profile,
-    )
-    .await?;
+    if let Some(loader) = &edit.loader
+        && !LOADERS.iter().any(|(_, name)| name == loader)
+    {
+        return Err(MineFetchError::Invalid(format!(
+            "Unknown loader: {loader} (use quilt, fabric, forge or neoforge)"
+        )));
+    }
 
-    // Regenerate cache
-    validate_cache(&working_profile).await?;
+    // Read the config again under the lock, it could change while the questions were asked
+    let config_lock = lock_config().await?;
+    let mut config = read_full_config().await?;
+    let index = config
+        .profile
+        .iter()
+        .position(|profile| profile.hash == old.hash)
+        .ok_or_else(|| {
+            MineFetchError::NotFound(format!("There's no profile named {}", old.name))
+        })?;
 
-    Ok(())
-}
+    if let Some(name) = &edit.name {
+        ensure_unique_name(&config.profile, name, Some(&old.hash))?;
+    }
 
-pub async fn list_cached() -> Result<(), Box<dyn Error>> {
-    let working_profile = build_working_profile().await?;
+    // The hash stays the same, so locks and --profile <hash> keep working
+    let profile = &mut config.profile[index];
+    if let Some(name) = edit.name {
+        profile.name = name;
+    }
+    if let Some(modsfolder) = edit.modsfolder {
+        profile.modsfolder = modsfolder;
+    }
+    if let Some(gameversion) = edit.gameversion {
+        profile.gameversion = gameversion;
+    }
+    if let Some(loader) = edit.loader {
+        profile.loader = loader;
+    }
 
-    let mods = list_mods_cached(&working_profile).await?;
-    let size = mods.len();
+    // The installed server loader doesn't fit a new target
+    if profile.gameversion != old.gameversion || profile.loader != old.loader {
+        profile.loader_version = None;
+    }
+
+    let new = profile.clone();
+
+    write_config(&config).await?;
+    drop(config_lock);
 
-    // Print text
     println!(
-        ":out: There are {}{}{} mods in profile {}:",
+        ":out: Profile {}{}{} [{} {}] [{}] saved",
         MFText::Bold,
-        size,
+        new.name,
         MFText::Reset,
-        working_profile.profile.name
+        new.loader,
+        new.gameversion,
+        new.modsfolder
     );
 
-    for (num, anymod) in mods.iter().enumerate() {
-        println!(
-            "[{}{}{}] {}{}{} ({})",
-            MFText::Bold,
-            num + 1,
-            MFText::Reset,
-            MFText::Bold,
-            anymod.title.clone().unwrap_or_default(),
-            MFText::Reset,
-            anymod.filename,
+    // Installed mods may not work with the new target
+    if old.gameversion == new.gameversion && old.loader == new.loader {
+        return Ok(());
+    }
+
+    let resolve = if edit.resolve {
+        true
+    } else if options.offline {
+        false
+    } else {
+        let prompt = format!(
+            "Re-resolve installed mods for {} {}?",
+            new.loader, new.gameversion
         );
+        confirm(&prompt, options, false).await?
+    };
+
+    if !resolve {
+        println!(":out: Installed mods were left as they are (use --resolve to re-resolve them)");
+        return Ok(());
     }
 
-    Ok(())
-}
+    // This part can't work without Modrinth
+    require_online(options, "profile edit --resolve")?;
 
-pub async fn fadd_lock() -> Result<(), Box<dyn Error>> {
-    // Create a working profile
-    let working_profile = build_working_profile().await?;
+    let working_profile = build_working_profile(&Options {
+        profile: Some(new.hash.clone()),
+        ..options.clone()
+    })
+    .await?;
 
-    // Add a lock through interactive menu
-    add_lock(&working_profile).await?;
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
 
-    Ok(())
+    resolve_for_profile(&working_profile).await
 }
 
-pub async fn rm_lock() -> Result<(), Box<dyn Error>> {
-    // Create a working profile
-    let working_profile = build_working_profile().await?;
+/// Asks for new profile values, an empty answer keeps the current one
+async fn ask_profile_edit(profile: &Profile) -> Result<ProfileEdit, MineFetchError> {
+    // Returns None if the answer is empty or the same
+    fn changed(answer: String, current: &str) -> Option<String> {
+        (!answer.is_empty() && answer != current).then_some(answer)
+    }
 
-    // Remove a lock through interactive menu
-    remove_lock(&working_profile).await?;
+    let name = ainput(&format!(":out: Name [{}]: ", profile.name)).await?;
+    let modsfolder = ainput(&format!(":out: Mods directory [{}]: ", profile.modsfolder)).await?;
+    let gameversion = ainput(&format!(
+        ":out: Minecraft version [{}]: ",
+        profile.gameversion
+    ))
+    .await?;
 
-    Ok(())
+    // Keep the current loader on top of the menu
+    let mut loaders: Vec<(String, &str)> =
+        vec![(format!("Keep {}", profile.loader), profile.loader.as_str())];
+    loaders.extend(
+        LOADERS
+            .iter()
+            .filter(|(_, name)| *name != profile.loader)
+            .map(|(title, name)| (title.to_string(), *name)),
+    );
+    let loader = select("Choose a loader", loaders).await?;
+
+    Ok(ProfileEdit {
+        name: changed(name, &profile.name),
+        modsfolder: changed(modsfolder, &profile.modsfolder),
+        gameversion: changed(gameversion, &profile.gameversion),
+        loader: changed(loader.to_string(), &profile.loader),
+        resolve: false,
+    })
 }
 
-pub async fn fedit_mod() -> Result<(), Box<dyn Error>> {
-    // Create a working profile
-    let working_profile = build_working_profile().await?;
+/// Replaces installed mods with versions for the profile's loader and game version
+async fn resolve_for_profile(working_profile: &WorkingProfile) -> Result<(), MineFetchError> {
+    let installed = list_mods_cached(working_profile).await.unwrap_or_default();
 
-    // Call an interactive dialog
-    edit_mod(&working_profile).await?;
+    let (updates, unsupported) = find_updates(working_profile).await?;
+
+    // Returns a title or a filename of the installed jar
+    let title = |hash: &String| {
+        installed
+            .iter()
+            .find(|anymod| &anymod.hash == hash)
+            .map(|anymod| anymod.title.clone().unwrap_or(anymod.filename.clone()))
+    };
+
+    // Unresolved jars are unknown to Modrinth anyway, warn only about the others
+    for hash in &unsupported {
+        if let Some(anymod) = installed
+            .iter()
+            .find(|anymod| &anymod.hash == hash && anymod.is_resolved())
+        {
+            eprintln!(
+                ":wrn: {} has no version for {} {}, it was left as is",
+                anymod.title.clone().unwrap_or(anymod.filename.clone()),
+                working_profile.profile.loader,
+                working_profile.profile.gameversion
+            );
+        }
+    }
+
+    let mut old_mods: Vec<String> = Vec::new();
+    let mut new_mods: Vec<Anymod> = Vec::new();
+
+    for update in updates {
+        if update.locked {
+            eprintln!(
+                ":wrn: {} is locked, it was left as is",
+                title(&update.old_hash).unwrap_or(update.new_mod.filename.clone())
+            );
+            continue;
+        }
+
+        old_mods.push(update.old_hash);
+        new_mods.push(update.new_mod);
+    }
+
+    if new_mods.is_empty() {
+        println!(":out: Nothing to re-resolve");
+        return Ok(());
+    }
+
+    println!(":out: Re-resolving {} mods...", new_mods.len());
+
+    replace_mods(old_mods.iter().collect(), new_mods, working_profile).await?;
+
+    // Regenerate cache
+    validate_cache(working_profile).await?;
 
     Ok(())
 }
 
-pub async fn ls_lock() -> Result<(), Box<dyn Error>> {
-    // Create a working profile
-    let working_profile = build_working_profile().await?;
-    let locks = list_locks(&working_profile).await?;
+/// Clones a profile into a new mods directory, copying or re-downloading every jar
+pub async fn clone_profile(clone: ProfileClone, options: &Options) -> Result<(), MineFetchError> {
+    let config = read_full_config().await?;
 
-    for (size, name, filename) in locks {
-        println!(
-            "[{}{}{}] {}{}{} ({})",
-            MFText::Bold,
-            size,
-            MFText::Reset,
-            MFText::Bold,
-            name,
-            MFText::Reset,
-            filename
-        );
+    // Find the source profile
+    let source = config
+        .profile
+        .iter()
+        .find(|profile| profile.name == clone.source || profile.hash == clone.source)
+        .cloned()
+        .ok_or_else(|| {
+            MineFetchError::NotFound(format!("There's no profile named {}", clone.source))
+        })?;
+
+    // --profile <name> has to find exactly one profile
+    let name = clone.name.unwrap_or(format!("{} (copy)", source.name));
+    ensure_unique_name(&config.profile, &name, None)?;
+
+    // The clone would lock the source's folder twice and wait for itself forever
+    let target_dir = Path::new(&clone.modsfolder);
+    if let Ok(target) = tokio::fs::canonicalize(target_dir).await
+        && tokio::fs::canonicalize(&source.modsfolder)
+            .await
+            .is_ok_and(|source_dir| source_dir == target)
+    {
+        return Err(MineFetchError::Invalid(format!(
+            "{} is the mods folder of {}, choose another directory",
+            clone.modsfolder, source.name
+        )));
+    }
+
+    // Don't mix the clone with somebody else's jars
+    if !get_hashes_or_empty(&clone.modsfolder).await?.is_empty() {
+        return Err(MineFetchError::Invalid(format!(
+            "{} already contains mods, choose an empty directory",
+            clone.modsfolder
+        )));
+    }
+
+    // Remember what was there, a failed clone removes only what it added
+    let created = !target_dir.exists();
+    tokio::fs::create_dir_all(target_dir)
+        .await
+        .map_err(|error| MineFetchError::io(target_dir, error))?;
+    let before = list_entries(target_dir).await;
+
+    // The clone gets its own hash and isn't activated
+    let new_profile = Profile {
+        active: false,
+        name,
+        modsfolder: clone.modsfolder.clone(),
+        gameversion: clone.gameversion.unwrap_or(source.gameversion.clone()),
+        loader: source.loader.clone(),
+        hash: generate_hash().await?,
+        instance: None,
+        loader_version: None,
+    };
+
+    // Mods of the source profile (work offline too)
+    let source_profile = build_working_profile(&Options {
+        profile: Some(source.hash.clone()),
+        ..options.clone()
+    })
+    .await?;
+
+    // The clone isn't in the config yet, it's saved only when it's complete
+    let target_profile = WorkingProfile {
+        profile: new_profile.clone(),
+        options: Options {
+            profile: Some(new_profile.hash.clone()),
+            ..options.clone()
+        },
+        ..source_profile.clone()
+    };
+
+    // The source is only read, the clone is written
+    let source_lock = lock_profile(&source, LockMode::Shared).await?;
+    let target_lock = lock_profile(&new_profile, LockMode::Exclusive).await?;
+
+    let filled = fill_clone(&source_profile, &target_profile, options).await;
+
+    drop(target_lock);
+    drop(source_lock);
+
+    let (copied, downloaded) = match filled {
+        Ok(counts) => counts,
+        Err(error) => {
+            remove_clone_leftovers(target_dir, created, &before).await;
+            return Err(error);
+        }
+    };
+
+    // Save the profile only when its mods are in place
+    let config_lock = lock_config().await?;
+    let mut config = read_full_config().await?;
+
+    // Another process could take the name while the jars were copied
+    if let Err(error) = ensure_unique_name(&config.profile, &new_profile.name, None) {
+        drop(config_lock);
+        remove_clone_leftovers(target_dir, created, &before).await;
+        return Err(error);
+    }
+
+    config.profile.push(new_profile.clone());
+    write_config(&config).await?;
+    drop(config_lock);
+
+    println!(
+        ":out: Cloned {} into {}{}{} [{}] (copied: {}, downloaded: {})",
+        source.name,
+        MFText::Bold,
+        new_profile.name,
+        MFText::Reset,
+        new_profile.modsfolder,
+        copied,
+        downloaded
+    );
+
+    // Another game version needs other versions of the mods
+    if new_profile.gameversion != source.gameversion {
+        require_online(options, "profile clone --game-version")?;
+        let _lock = lock_profile(&new_profile, LockMode::Exclusive).await?;
+        resolve_for_profile(&target_profile).await?;
+    }
+
+    Ok(())
+}
+
+/// Copies (or downloads) the source's jars, locks and cache into the clone's folder.
+/// Returns how many jars were copied and downloaded
+async fn fill_clone(
+    source_profile: &WorkingProfile,
+    target_profile: &WorkingProfile,
+    options: &Options,
+) -> Result<(usize, usize), MineFetchError> {
+    let source = &source_profile.profile;
+    let target_dir = Path::new(&target_profile.profile.modsfolder);
+
+    let installed = list_mods_cached(source_profile).await.unwrap_or_default();
+
+    let mut copied: usize = 0;
+    let mut redownload: Vec<Anymod> = Vec::new();
+
+    for (path, hash) in get_hashed_jars(&source.modsfolder)
+        .await
+        .unwrap_or_default()
+    {
+        let Some(filename) = path.file_name() else {
+            continue;
+        };
+        let destination = target_dir.join(filename);
+
+        // Copy and check that the copy is the same file
+        let verified = match tokio::fs::copy(&path, &destination).await {
+            Ok(_) => {
+                let copy = destination.clone();
+                spawn_blocking(move || calculate_sha1(&copy))
+                    .await?
+                    .is_ok_and(|copy_hash| copy_hash == hash)
+            }
+            Err(_) => false,
+        };
+
+        if verified {
+            copied += 1;
+            continue;
+        }
+
+        let _ = tokio::fs::remove_file(&destination).await;
+
+        // A broken copy can be downloaded again if Modrinth knows the jar
+        match installed
+            .iter()
+            .find(|anymod| anymod.hash == hash && anymod.is_resolved())
+        {
+            Some(anymod) => redownload.push(anymod.clone()),
+            None => {
+                return Err(MineFetchError::Io {
+                    path: Some(path.display().to_string()),
+                    message: "Couldn't copy the jar and it can't be downloaded".into(),
+                });
+            }
+        }
+    }
+
+    // Locks and cache are keyed by jar hashes, so they stay valid
+    for file in ["locks.toml", "cache.toml"] {
+        let from = Path::new(&source.modsfolder).join(file);
+        if from.exists() {
+            let to = target_dir.join(file);
+            tokio::fs::copy(&from, &to)
+                .await
+                .map_err(|error| MineFetchError::io(&to, error))?;
+        }
+    }
+
+    let downloaded = redownload.len();
+    if !redownload.is_empty() {
+        require_online(options, "profile clone")?;
+        download_multiple_mods(redownload, Arc::new(target_profile.clone())).await?;
+    }
+
+    Ok((copied, downloaded))
+}
+
+/// Names of the entries in a directory
+async fn list_entries(dir: &Path) -> HashSet<OsString> {
+    let mut names = HashSet::new();
+
+    if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            names.insert(entry.file_name());
+        }
+    }
+
+    names
+}
+
+/// Undoes a failed clone and tells the user what couldn't be removed
+async fn remove_clone_leftovers(dir: &Path, created: bool, before: &HashSet<OsString>) {
+    // The folder is ours, nobody else's files are inside
+    if created {
+        match tokio::fs::remove_dir_all(dir).await {
+            Ok(()) => println!(":out: Removed the unfinished clone in {}", dir.display()),
+            Err(error) => eprintln!(
+                ":wrn: Couldn't remove the unfinished clone in {}: {}, delete it by hand",
+                dir.display(),
+                error
+            ),
+        }
+        return;
+    }
+
+    let mut left: Vec<PathBuf> = Vec::new();
+
+    for name in list_entries(dir).await.difference(before) {
+        let path = dir.join(name);
+        if tokio::fs::remove_file(&path).await.is_err() {
+            left.push(path);
+        }
+    }
+
+    if left.is_empty() {
+        println!(
+            ":out: Removed the files of the unfinished clone from {}",
+            dir.display()
+        );
+        return;
+    }
+
+    eprintln!(
+        ":wrn: These files of the unfinished clone couldn't be removed, delete them by hand:"
+    );
+    for path in left {
+        eprintln!("      {}", path.display());
+    }
+}
+
+/// Returns hashes of the jars in the directory (none if it doesn't exist)
+async fn get_hashes_or_empty(path: &str) -> Result<Vec<String>, MineFetchError> {
+    match get_hashes(path).await {
+        Ok(hashes) => Ok(hashes),
+        Err(MineFetchError::NotFound(_)) => Ok(Vec::new()),
+        Err(error) => Err(error),
+    }
+}
+
+pub async fn upgrade(
+    include: &[String],
+    exclude: &[String],
+    options: &Options,
+) -> Result<(), MineFetchError> {
+    // This command can't work without Modrinth
+    require_online(options, "update")?;
+
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
+
+    // Returns a list of new files of mods to install
+    let (old_mods, new_mods) = upgrade_mods(&working_profile).await?;
+
+    // Names are resolved against the installed mods
+    let installed = list_mods_cached(&working_profile).await.unwrap_or_default();
+
+    // Keep only the named mods and drop the excluded ones
+    let (old_mods, new_mods) = filter_updates(
+        old_mods,
+        new_mods,
+        include,
+        exclude,
+        &installed,
+        &working_profile,
+    )
+    .await?;
+
+    // If empty then there're no mods to update
+    if new_mods.is_empty() {
+        println!(":out: All mods are up to date!");
+        return Ok(());
+    }
+
+    // Show what's going to be pulled in (only a help for the review, so it can't stop the update)
+    if let Err(error) = print_changelogs(&installed, &old_mods, &new_mods, &working_profile).await {
+        eprintln!(":wrn: Couldn't show changelogs: {error}");
+    }
+
+    // Let the user review the changes (scripts have to pass --yes)
+    let prompt = format!("Apply {} updates?", new_mods.len());
+    if !confirm(&prompt, options, true).await? {
+        return Err(MineFetchError::Cancelled);
+    }
+
+    // Download 'files'
+    replace_mods(old_mods.iter().collect(), new_mods, &working_profile).await?;
+
+    // Regenerate cache
+    validate_cache(&working_profile).await?;
+
+    Ok(())
+}
+
+/// Keeps only the updates of the included mods (all if empty) and drops the excluded ones
+async fn filter_updates(
+    old_mods: Vec<String>,
+    new_mods: Vec<Anymod>,
+    include: &[String],
+    exclude: &[String],
+    installed: &[Anymod],
+    working_profile: &WorkingProfile,
+) -> Result<(Vec<String>, Vec<Anymod>), MineFetchError> {
+    if include.is_empty() && exclude.is_empty() {
+        return Ok((old_mods, new_mods));
+    }
+
+    // Turn every name into a project id
+    let mut included: Vec<String> = Vec::new();
+    for modname in include {
+        included.push(
+            find_installed(modname, installed, working_profile)
+                .await?
+                .project_id,
+        );
+    }
+
+    // Excluding a mod that isn't installed is fine (it's common with --all-profiles)
+    let mut excluded: Vec<String> = Vec::new();
+    for modname in exclude {
+        match find_installed(modname, installed, working_profile).await {
+            Ok(anymod) => excluded.push(anymod.project_id),
+            Err(MineFetchError::NotFound(_)) => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(old_mods
+        .into_iter()
+        .zip(new_mods)
+        .filter(|(_, new_mod)| {
+            (included.is_empty() || included.contains(&new_mod.project_id))
+                && !excluded.contains(&new_mod.project_id)
+        })
+        .unzip())
+}
+
+/// Shows changelogs of pending updates (for all mods or for one)
+pub async fn changelog(modname: Option<&str>, options: &Options) -> Result<(), MineFetchError> {
+    // This command can't work without Modrinth
+    require_online(options, "changelog")?;
+
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Writers wait until this is done
+    let _lock = lock_profile(&working_profile.profile, LockMode::Shared).await?;
+
+    let (old_mods, new_mods) = upgrade_mods(&working_profile).await?;
+    let installed = list_mods_cached(&working_profile).await?;
+
+    // Keep only the selected mod
+    let include: Vec<String> = modname.map(str::to_string).into_iter().collect();
+    let (old_mods, new_mods) = filter_updates(
+        old_mods,
+        new_mods,
+        &include,
+        &[],
+        &installed,
+        &working_profile,
+    )
+    .await?;
+
+    if new_mods.is_empty() {
+        println!(":out: Nothing to update");
+        return Ok(());
+    }
+
+    print_changelogs(&installed, &old_mods, &new_mods, &working_profile).await
+}
+
+/// Lists available updates without applying them.
+/// Returns true if there's anything that 'update' would install
+pub async fn outdated(options: &Options) -> Result<bool, MineFetchError> {
+    let rows = find_outdated(options).await?;
+
+    // Locked mods are shown, but 'update' won't touch them
+    let available = rows.iter().any(|row| !row.locked);
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(available);
+    }
+
+    print_outdated(rows);
+
+    Ok(available)
+}
+
+/// Collects available updates of the selected profile, sorted by title
+async fn find_outdated(options: &Options) -> Result<Vec<OutdatedMod>, MineFetchError> {
+    // This command can't work without Modrinth
+    require_online(options, "outdated")?;
+
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Writers wait until this is done
+    let _lock = lock_profile(&working_profile.profile, LockMode::Shared).await?;
+
+    let (updates, _) = find_updates(&working_profile).await?;
+    let installed = list_mods_cached(&working_profile).await?;
+
+    // Match every update with the installed mod
+    let mut rows: Vec<OutdatedMod> = updates
+        .iter()
+        .map(|update| {
+            let old_mod = installed
+                .iter()
+                .find(|anymod| anymod.hash == update.old_hash);
+
+            OutdatedMod {
+                title: old_mod
+                    .and_then(|anymod| anymod.title.clone())
+                    .unwrap_or(update.new_mod.filename.clone()),
+                project_id: update.new_mod.project_id.clone(),
+                installed_version: old_mod
+                    .map(|anymod| anymod.version_name.clone())
+                    .unwrap_or("?".to_string()),
+                available_version: update.new_mod.version_name.clone(),
+                filename: update.new_mod.filename.clone(),
+                channel: update.channel.clone(),
+                locked: update.locked,
+            }
+        })
+        .collect();
+
+    rows.sort_by_key(|row| row.title.to_lowercase());
+
+    Ok(rows)
+}
+
+/// Prints available updates as a table
+fn print_outdated(rows: Vec<OutdatedMod>) {
+    if rows.is_empty() {
+        println!(":out: All mods are up to date!");
+        return;
+    }
+
+    let header = ["Mod", "Installed", "Available", "Channel", "Locked"];
+
+    let cells: Vec<[String; 5]> = rows
+        .into_iter()
+        .map(|row| {
+            [
+                row.title,
+                row.installed_version,
+                row.available_version,
+                row.channel,
+                if row.locked { "yes" } else { "no" }.to_string(),
+            ]
+        })
+        .collect();
+
+    // Width of every column is the width of its longest cell
+    let mut widths = header.map(|title| title.chars().count());
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header_line: Vec<String> = header
+        .iter()
+        .zip(widths)
+        .map(|(title, width)| format!("{title:<width$}"))
+        .collect();
+
+    println!(
+        "{}{}{}",
+        MFText::Bold,
+        header_line.join("  ").trim_end(),
+        MFText::Reset
+    );
+
+    for row in &cells {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+/// Finds an installed mod by project id, title or slug
+async fn find_installed(
+    modname: &str,
+    installed: &[Anymod],
+    working_profile: &WorkingProfile,
+) -> Result<Anymod, MineFetchError> {
+    let found = installed.iter().find(|anymod| {
+        anymod.project_id == modname
+            || anymod
+                .title
+                .as_ref()
+                .is_some_and(|title| title.eq_ignore_ascii_case(modname))
+    });
+
+    if let Some(anymod) = found {
+        return Ok(anymod.clone());
+    }
+
+    // Slugs aren't cached, ask Modrinth for the project id
+    let project = get_project(modname, working_profile).await?;
+
+    installed
+        .iter()
+        .find(|anymod| anymod.project_id == project.id)
+        .cloned()
+        .ok_or_else(|| MineFetchError::NotFound(format!("The mod {modname} isn't installed")))
+}
+
+/// Prints changelogs of all versions between the installed and the new one, per mod
+async fn print_changelogs(
+    installed: &[Anymod],
+    old_mods: &[String],
+    new_mods: &[Anymod],
+    working_profile: &WorkingProfile,
+) -> Result<(), MineFetchError> {
+    for (old_hash, new_mod) in old_mods.iter().zip(new_mods) {
+        let old_mod = installed.iter().find(|anymod| &anymod.hash == old_hash);
+
+        let (title, old_version, old_version_id) = match old_mod {
+            Some(old_mod) => (
+                old_mod.title.clone().unwrap_or(old_mod.filename.clone()),
+                old_mod.version_name.as_str(),
+                old_mod.version_id.as_str(),
+            ),
+            None => (new_mod.filename.clone(), "?", ""),
+        };
+
+        println!(
+            "\n{}{}{}: {} -> {}",
+            MFText::Bold,
+            title,
+            MFText::Reset,
+            old_version,
+            new_mod.version_name
+        );
+
+        for version in get_changelogs(old_version_id, new_mod, working_profile).await? {
+            println!(
+                "\n  {}{}{} ({}, {})",
+                MFText::Underline,
+                version.name,
+                MFText::Reset,
+                version.version_type,
+                version
+                    .date_published
+                    .get(..10)
+                    .unwrap_or(&version.date_published)
+            );
+
+            match version.changelog.as_deref().map(str::trim) {
+                Some(text) if !text.is_empty() => {
+                    for line in text.lines() {
+                        println!("    {line}");
+                    }
+                }
+                _ => println!("    (no changelog)"),
+            }
+        }
+    }
+
+    println!();
+
+    Ok(())
+}
+
+pub async fn list_cached(options: &Options) -> Result<(), MineFetchError> {
+    let working_profile = build_working_profile(options).await?;
+
+    // Writers wait until this is done
+    let _lock = lock_profile(&working_profile.profile, LockMode::Shared).await?;
+
+    let mods = list_mods_cached(&working_profile).await?;
+    let size = mods.len();
+
+    // Print text
+    println!(
+        ":out: There are {}{}{} mods in profile {}:",
+        MFText::Bold,
+        size,
+        MFText::Reset,
+        working_profile.profile.name
+    );
+
+    for (num, anymod) in mods.iter().enumerate() {
+        println!(
+            "[{}{}{}] {}{}{} ({}){}",
+            MFText::Bold,
+            num + 1,
+            MFText::Reset,
+            MFText::Bold,
+            anymod.title.clone().unwrap_or(anymod.filename.clone()),
+            MFText::Reset,
+            anymod.filename,
+            if anymod.is_resolved() {
+                ""
+            } else {
+                " [unresolved]"
+            }
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn fadd_lock(options: &Options) -> Result<(), MineFetchError> {
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
+
+    // Add a lock through interactive menu
+    add_lock(&working_profile).await?;
+
+    Ok(())
+}
+
+pub async fn rm_lock(options: &Options) -> Result<(), MineFetchError> {
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
+
+    // Remove a lock through interactive menu
+    remove_lock(&working_profile).await?;
+
+    Ok(())
+}
+
+pub async fn fedit_mod(options: &Options) -> Result<(), MineFetchError> {
+    // This command can't work without Modrinth
+    require_online(options, "edit")?;
+
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
+
+    // Call an interactive dialog
+    edit_mod(&working_profile).await?;
+
+    Ok(())
+}
+
+pub async fn ls_lock(options: &Options) -> Result<(), MineFetchError> {
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Writers wait until this is done
+    let _lock = lock_profile(&working_profile.profile, LockMode::Shared).await?;
+    let locks = list_locks(&working_profile).await?;
+
+    for (size, name, filename) in locks {
+        println!(
+            "[{}{}{}] {}{}{} ({})",
+            MFText::Bold,
+            size,
+            MFText::Reset,
+            MFText::Bold,
+            name,
+            MFText::Reset,
+            filename
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes minefetch.lock with the exact files of the installed mods
+pub async fn generate_lockfile(
+    output: Option<&str>,
+    options: &Options,
+) -> Result<(), MineFetchError> {
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Writers wait until this is done
+    let _lock = lock_profile(&working_profile.profile, LockMode::Shared).await?;
+
+    let mut mods = list_mods_cached(&working_profile).await?;
+
+    // Only Modrinth files can be downloaded again
+    mods.retain(|anymod| {
+        if !anymod.is_resolved() {
+            eprintln!(
+                ":wrn: {} isn't known to Modrinth, it's left out of the lockfile",
+                anymod.filename
+            );
+        }
+        anymod.is_resolved()
+    });
+
+    let lockfile = Lockfile::new(
+        &working_profile.profile.gameversion,
+        &working_profile.profile.loader,
+        &mods,
+    );
+
+    let path = PathBuf::from(output.unwrap_or(LOCKFILE_NAME));
+    write_lockfile(&path, &lockfile).await?;
+
+    println!(
+        ":out: Wrote {}{}{} mods to {}",
+        MFText::Bold,
+        lockfile.mods.len(),
+        MFText::Reset,
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Makes the mods folder contain exactly the files from minefetch.lock
+pub async fn install_locked(
+    lockfile: Option<&str>,
+    options: &Options,
+) -> Result<(), MineFetchError> {
+    let path = find_lockfile(lockfile)?;
+    let lockfile = read_lockfile(&path).await?;
+
+    println!(":out: Installing from {}", path.display());
+
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    let profile = &working_profile.profile;
+
+    // An empty folder is fine here
+    tokio::fs::create_dir_all(&profile.modsfolder)
+        .await
+        .map_err(|error| MineFetchError::io(&profile.modsfolder, error))?;
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(profile, LockMode::Exclusive).await?;
+
+    if lockfile.gameversion != profile.gameversion || lockfile.loader != profile.loader {
+        eprintln!(
+            ":wrn: {} was made for {} {}, but the profile {} has {} {}",
+            path.display(),
+            lockfile.loader,
+            lockfile.gameversion,
+            profile.name,
+            profile.loader,
+            profile.gameversion
+        );
+    }
+
+    let jars = get_hashed_jars(&profile.modsfolder).await?;
+
+    let locked_hashes: HashSet<&String> = lockfile
+        .mods
+        .iter()
+        .map(|locked| &locked.hashes.sha1)
+        .collect();
+
+    // Files that aren't in the folder yet
+    let missing: Vec<Anymod> = lockfile
+        .mods
+        .iter()
+        .filter(|locked| !jars.iter().any(|(_, hash)| hash == &locked.hashes.sha1))
+        .map(LockedMod::to_anymod)
+        .collect();
+
+    // Files that aren't in the lockfile
+    let extra: Vec<&(PathBuf, String)> = jars
+        .iter()
+        .filter(|(_, hash)| !locked_hashes.contains(hash))
+        .collect();
+
+    if missing.is_empty() && extra.is_empty() {
+        println!(":out: The mods folder already matches the lockfile");
+        return Ok(());
+    }
+
+    for anymod in &missing {
+        println!(
+            "  + {} {}",
+            anymod.title.as_deref().unwrap_or(&anymod.filename),
+            anymod.version_name
+        );
+    }
+
+    for (jar, _) in &extra {
+        println!(
+            "  - {}",
+            jar.file_name().unwrap_or_default().to_string_lossy()
+        );
+    }
+
+    // Nothing can be downloaded offline
+    if !missing.is_empty() {
+        require_online(options, "install --locked")?;
+    }
+
+    // Let the user review removals (scripts have to pass --yes)
+    let prompt = format!("Remove {} mods that aren't in the lockfile?", extra.len());
+    if !extra.is_empty() && !confirm(&prompt, options, true).await? {
+        return Err(MineFetchError::Cancelled);
+    }
+
+    // Fails if any file can't be fetched or doesn't match its hash
+    download_multiple_mods(missing, Arc::new(working_profile.clone())).await?;
+
+    let extra_hashes: Vec<&String> = extra.iter().map(|(_, hash)| hash).collect();
+    remove_mods_by_hash(&profile.modsfolder, &extra_hashes).await?;
+
+    // Check the result once more
+    let hashes = get_hashes(&profile.modsfolder).await?;
+
+    if let Some(locked) = lockfile
+        .mods
+        .iter()
+        .find(|locked| !hashes.contains(&locked.hashes.sha1))
+    {
+        return Err(MineFetchError::NotFound(format!(
+            "{} with sha1 {} isn't in the mods folder after installing",
+            locked.filename, locked.hashes.sha1
+        )));
+    }
+
+    // Regenerate cache (done later if offline)
+    if !options.offline {
+        validate_cache(&working_profile).await?;
+    }
+
+    Ok(())
+}
+
+/// Downloads the server loader for the profile's Minecraft version into the instance root.
+/// With 'update' it's only done if there's a newer version than the recorded one
+pub async fn install_loader(
+    version: Option<&str>,
+    update: bool,
+    options: &Options,
+) -> Result<(), MineFetchError> {
+    let command = if update {
+        "loader update"
+    } else {
+        "loader install"
+    };
+
+    // This command can't work without the loader's metadata service
+    require_online(options, command)?;
+
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+    let profile = &working_profile.profile;
+
+    // The instance root is the folder that holds 'mods'
+    let root = match Path::new(&profile.modsfolder).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    // A launcher runs the loader itself, a server jar there would only be clutter
+    if let Some(reason) = client_instance(profile, &root) {
+        return Err(MineFetchError::Invalid(format!(
+            "The profile {} is a client instance ({reason}), 'minefetch loader' is for servers",
+            profile.name
+        )));
+    }
+
+    if update && profile.loader_version.is_none() {
+        return Err(MineFetchError::Invalid(format!(
+            "The profile {} has no loader installed yet, use 'minefetch loader install'",
+            profile.name
+        )));
+    }
+
+    let release = find_release(
+        &working_profile.client,
+        profile,
+        &working_profile.settings,
+        version,
+    )
+    .await?;
+
+    if update && profile.loader_version.as_deref() == Some(release.version.as_str()) {
+        println!(
+            ":out: {} {} is the latest version for Minecraft {}",
+            profile.loader, release.version, profile.gameversion
+        );
+        return Ok(());
+    }
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(profile, LockMode::Exclusive).await?;
+
+    println!(
+        ":out: Downloading {} {} for Minecraft {}...",
+        profile.loader, release.version, profile.gameversion
+    );
+
+    download_release(&working_profile.client, &release, &root).await?;
+
+    // Remember the version, so 'loader update' knows what's installed
+    let config_lock = lock_config().await?;
+    let mut config = read_full_config().await?;
+
+    match config
+        .profile
+        .iter_mut()
+        .find(|candidate| candidate.hash == profile.hash)
+    {
+        Some(candidate) => {
+            candidate.loader_version = Some(release.version.clone());
+            write_config(&config).await?;
+        }
+        None => eprintln!(
+            ":wrn: The profile {} is defined in {}, so the loader version isn't recorded",
+            profile.name, MARKER_NAME
+        ),
+    }
+
+    drop(config_lock);
+
+    println!(
+        ":out: Saved {}{}{}",
+        MFText::Bold,
+        root.join(&release.filename).display(),
+        MFText::Reset
+    );
+
+    if let Some(next_step) = release.next_step {
+        println!(
+            ":out: Finish the setup in {} with: {next_step}",
+            root.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Tells why the profile looks like a launcher's instance (None for servers)
+fn client_instance(profile: &Profile, root: &Path) -> Option<String> {
+    if let Some(instance) = &profile.instance {
+        return Some(format!("imported from {instance}"));
+    }
+
+    let name = root.file_name().and_then(|name| name.to_str());
+    if matches!(name, Some(".minecraft" | "minecraft")) {
+        return Some(format!("{} is a launcher's game folder", root.display()));
+    }
+
+    if root.join("launcher_profiles.json").exists() {
+        return Some(format!("{} has launcher_profiles.json", root.display()));
+    }
+
+    None
+}
+
+/// A command that can be run for every profile with --all-profiles
+pub enum ProfileCommand<'a> {
+    Update {
+        include: &'a [String],
+        exclude: &'a [String],
+    },
+    Outdated,
+    List,
+}
+
+/// Runs a command for every profile and prints a combined summary.
+/// Returns true if 'outdated' found updates in any profile
+pub async fn for_all_profiles(
+    command: ProfileCommand<'_>,
+    options: &Options,
+) -> Result<bool, MineFetchError> {
+    let config = read_full_config().await?;
+
+    if config.profile.is_empty() {
+        return Err(MineFetchError::NotFound("There are no profiles yet".into()));
+    }
+
+    let mut failed: Vec<(String, MineFetchError)> = Vec::new();
+    let mut outdated_profiles: usize = 0;
+
+    // 'outdated --json' prints one document for all profiles
+    let mut json_rows: Vec<serde_json::Value> = Vec::new();
+
+    for profile in &config.profile {
+        // Select the profile by hash, a name could also match another profile's hash
+        let profile_options = Options {
+            profile: Some(profile.hash.clone()),
+            all_profiles: false,
+            ..options.clone()
+        };
+
+        if !options.json {
+            println!(
+                "\n{}{}== {} [{} {}] =={}",
+                MFText::Bold,
+                MFText::Underline,
+                profile.name,
+                profile.loader,
+                profile.gameversion,
+                MFText::Reset
+            );
+        }
+
+        let result = match command {
+            ProfileCommand::Update { include, exclude } => {
+                upgrade(include, exclude, &profile_options).await
+            }
+            ProfileCommand::List => list_cached(&profile_options).await,
+            ProfileCommand::Outdated => match find_outdated(&profile_options).await {
+                Ok(rows) => {
+                    if rows.iter().any(|row| !row.locked) {
+                        outdated_profiles += 1;
+                    }
+
+                    if options.json {
+                        json_rows.push(serde_json::json!({
+                            "profile": profile.name,
+                            "updates": rows,
+                        }));
+                    } else {
+                        print_outdated(rows);
+                    }
+
+                    Ok(())
+                }
+                Err(error) => Err(error),
+            },
+        };
+
+        if let Err(error) = result {
+            eprintln!(":err: {}: {}", profile.name, error);
+            failed.push((profile.name.clone(), error));
+        }
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&json_rows)?);
+    } else {
+        // Print the combined summary
+        let mut summary = format!(
+            "\n:out: Profiles: {}, succeeded: {}, failed: {}",
+            config.profile.len(),
+            config.profile.len() - failed.len(),
+            failed.len()
+        );
+
+        if let ProfileCommand::Outdated = command {
+            summary.push_str(&format!(", with updates: {outdated_profiles}"));
+        }
+
+        println!("{summary}");
+    }
+
+    // Return the first error, if any
+    if let Some((_, error)) = failed.into_iter().next() {
+        return Err(error);
+    }
+
+    Ok(outdated_profiles > 0)
+}
+
+/// Makes the mods folder match minefetch.toml
+pub async fn sync(args: SyncArgs, options: &Options) -> Result<(), MineFetchError> {
+    // This command can't work without Modrinth
+    require_online(options, "sync")?;
+
+    let manifest_path = match &args.manifest {
+        Some(path) => PathBuf::from(path),
+        None => find_manifest().await?,
+    };
+
+    let manifest = read_manifest(&manifest_path).await?;
+
+    println!(":out: Syncing with {}", manifest_path.display());
+
+    // Create a working profile
+    let working_profile = build_working_profile(options).await?;
+
+    // Other processes must not touch this profile meanwhile
+    let _lock = lock_profile(&working_profile.profile, LockMode::Exclusive).await?;
+
+    let installed = list_mods_cached(&working_profile).await?;
+    let locks = get_locks(&working_profile.profile)
+        .await
+        .unwrap_or_default();
+
+    // Project ids that must stay in the folder
+    let mut wanted: HashSet<String> = HashSet::new();
+
+    let mut install: Vec<Anymod> = Vec::new();
+    let mut update: Vec<(Anymod, Anymod)> = Vec::new();
+
+    // Required dependencies of everything that stays
+    let mut dependencies: Vec<Dependency> = Vec::new();
+
+    // Mods for the other side are treated as if they weren't listed
+    let entries: Vec<(&String, ManifestMod)> = manifest
+        .mods
+        .iter()
+        .map(|(slug, entry)| (slug, entry.to_mod()))
+        .filter(|(_, entry)| entry.is_for_side(args.side.as_deref()))
+        .collect();
+
+    // Get the newest version that fits every entry
+    let mut newest: Vec<(&String, Version, Vec<String>)> = Vec::new();
+
+    for (slug, entry) in &entries {
+        let matching: Vec<Version> = fetch_versions(slug, &working_profile)
+            .await?
+            .into_iter()
+            .filter(|version| entry.matches(version))
+            .collect();
+
+        let version_ids = matching.iter().map(|version| version.id.clone()).collect();
+
+        let version = matching.into_iter().next().ok_or_else(|| {
+            MineFetchError::NotFound(format!(
+                "No version of {slug} matches {} for {} {}",
+                entry.version.as_deref().unwrap_or("*"),
+                working_profile.profile.loader,
+                working_profile.profile.gameversion
+            ))
+        })?;
+
+        newest.push((slug, version, version_ids));
+    }
+
+    // Get all titles with one request
+    let titles: HashMap<String, String> = get_projects_name(
+        &working_profile,
+        newest
+            .iter()
+            .map(|(_, version, _)| &version.project_id)
+            .collect(),
+    )
+    .await?
+    .into_iter()
+    .map(|project| (project.id, project.title))
+    .collect();
+
+    for (slug, version, version_ids) in newest {
+        let title = titles.get(&version.project_id).cloned();
+        let new_mod = version_to_anymod(version, title.or(Some(slug.clone())))?;
+
+        wanted.insert(new_mod.project_id.clone());
+
+        let current = installed
+            .iter()
+            .find(|anymod| anymod.project_id == new_mod.project_id);
+
+        let keep = match current {
+            // The installed version still fits the constraint
+            Some(current) if version_ids.contains(&current.version_id) => Some(current),
+
+            // Locked mods are never touched
+            Some(current) if locks.contains(&current.hash) => {
+                eprintln!(
+                    ":wrn: {} is locked and doesn't match {}, leaving it as is",
+                    new_mod.title.as_deref().unwrap_or(slug),
+                    manifest_path.display()
+                );
+                Some(current)
+            }
+
+            Some(current) => {
+                update.push((current.clone(), new_mod.clone()));
+                None
+            }
+
+            None => {
+                install.push(new_mod.clone());
+                None
+            }
+        };
+
+        let depends = match keep {
+            Some(current) => &current.depends,
+            None => &new_mod.depends,
+        };
+
+        dependencies.extend(
+            depends
+                .iter()
+                .flatten()
+                .filter(|dependency| dependency.dependency_type == "required")
+                .cloned(),
+        );
+    }
+
+    // Resolve what the listed mods need
+    if !dependencies.is_empty() {
+        for (dep, dep_type) in
+            get_dependencies_recursive(&dependencies, &installed, &working_profile).await?
+        {
+            if dep_type != "required" || !wanted.insert(dep.project_id.clone()) {
+                continue;
+            }
+
+            // An installed dependency of any version is fine
+            if !installed
+                .iter()
+                .any(|anymod| anymod.project_id == dep.project_id)
+            {
+                install.push(dep);
+            }
+        }
+    }
+
+    let mut remove: Vec<Anymod> = Vec::new();
+
+    for anymod in &installed {
+        let title = anymod.title.as_deref().unwrap_or(&anymod.filename);
+
+        if !anymod.is_resolved() {
+            eprintln!(":wrn: {title} isn't known to Modrinth, leaving it as is");
+        } else if wanted.contains(&anymod.project_id) {
+            continue;
+        } else if locks.contains(&anymod.hash) {
+            eprintln!(":wrn: {title} is locked and isn't in the manifest, leaving it as is");
+        } else {
+            remove.push(anymod.clone());
+        }
+    }
+
+    if install.is_empty() && update.is_empty() && remove.is_empty() {
+        println!(":out: The mods folder already matches the manifest");
+        return Ok(());
+    }
+
+    // Show the plan
+    for anymod in &install {
+        println!(
+            "  + {} {}",
+            anymod.title.as_deref().unwrap_or(&anymod.filename),
+            anymod.version_name
+        );
+    }
+
+    for (old_mod, new_mod) in &update {
+        println!(
+            "  ~ {} {} -> {}",
+            new_mod.title.as_deref().unwrap_or(&new_mod.filename),
+            old_mod.version_name,
+            new_mod.version_name
+        );
+    }
+
+    for anymod in &remove {
+        println!(
+            "  - {} {}",
+            anymod.title.as_deref().unwrap_or(&anymod.filename),
+            anymod.version_name
+        );
+    }
+
+    if args.dry_run {
+        return Ok(());
+    }
+
+    // Let the user review the changes (scripts have to pass --yes)
+    if !confirm("Apply these changes?", options, true).await? {
+        return Err(MineFetchError::Cancelled);
+    }
+
+    install.extend(update.iter().map(|(_, new_mod)| new_mod.clone()));
+
+    let downloads = download_mods_reported(install, Arc::new(working_profile.clone())).await?;
+
+    // Old files of updated mods go away (if the new file is in place) together with the removed ones
+    let old_hashes: Vec<&String> = update
+        .iter()
+        .filter(|(_, new_mod)| downloads.done.contains(&new_mod.hash))
+        .map(|(old_mod, _)| &old_mod.hash)
+        .chain(remove.iter().map(|anymod| &anymod.hash))
+        .collect();
+
+    remove_mods_by_hash(&working_profile.profile.modsfolder, &old_hashes).await?;
+
+    // Regenerate cache
+    validate_cache(&working_profile).await?;
+
+    downloads.into_result()
+}
+
+/// Asks a yes / no question. --yes answers it. Without a terminal
+/// a 'destructive' question is an error and any other is answered no
+async fn confirm(
+    prompt: &str,
+    options: &Options,
+    destructive: bool,
+) -> Result<bool, MineFetchError> {
+    if options.yes {
+        return Ok(true);
+    }
+
+    if !std::io::stdin().is_terminal() {
+        if destructive {
+            return Err(MineFetchError::Invalid(format!(
+                "There's no terminal to answer \"{prompt}\", pass --yes"
+            )));
+        }
+        return Ok(false);
+    }
+
+    let menu = vec![("Yes".to_string(), true), ("No".to_string(), false)];
+    select(prompt, menu).await
+}
+
+/// Returns an error if the command needs the network but --offline is set
+fn require_online(options: &Options, command: &str) -> Result<(), MineFetchError> {
+    if options.offline {
+        return Err(MineFetchError::Invalid(format!(
+            "'minefetch {command}' needs the network and can't be used with --offline"
+        )));
     }
 
     Ok(())
@@ -1,70 +1,146 @@
 // Internal modules
-use crate::consts::HELP_MESSAGE;
+use crate::cli::{CommandSpec, FlagSpec, command_name, usage};
+use crate::consts::{COMMANDS, GLOBAL_FLAGS};
 use crate::mfio::MFText;
 
-// Help message root
-pub struct Help {
-    pub header: &'static str,
-    pub program_name: &'static str,
-    pub message: &'static [&'static Message],
-}
+// Program name in the help
+const PROGRAM_NAME: &str = "minefetch";
+
+// One line of the help: name and description
+type Row = (String, &'static str);
 
-// Message structure
-pub struct Message {
-    pub name: &'static str,
-    pub description: &'static str,
+/// Display the help of a command (the list of commands if the path is empty)
+pub async fn display_help(path: &[&CommandSpec]) {
+    match path.last() {
+        None => display_commands(),
+        Some(command) => display_command(path, command),
+    }
 }
 
-/// Display a help message
-pub async fn display_help() -> () {
-    // The amount of space that the largest 'name' takes
-    let space_size = find_the_largest_msg(&HELP_MESSAGE);
+/// The list of all commands
+fn display_commands() {
+    let mut rows: Vec<Row> = Vec::new();
 
-    // Print the header
+    for command in COMMANDS.iter().filter(|command| !command.hidden) {
+        if command.subcommands.is_empty() {
+            rows.push((
+                join(command.name, &command.usage_args()),
+                command.description,
+            ));
+            continue;
+        }
+
+        // 'profile create', 'profile edit'...
+        for subcommand in command.subcommands.iter().filter(|command| !command.hidden) {
+            rows.push((
+                join(
+                    &format!("{} {}", command.name, subcommand.name),
+                    &subcommand.usage_args(),
+                ),
+                subcommand.description,
+            ));
+        }
+    }
+
+    print_rows("Commands:", Some(PROGRAM_NAME), &rows);
+
+    print_rows("Global flags:", None, &flag_rows(GLOBAL_FLAGS));
+
+    println!(
+        "\tRun '{} <command> --help' to see flags of a command",
+        PROGRAM_NAME
+    );
+}
+
+/// The help of one command
+fn display_command(path: &[&CommandSpec], command: &CommandSpec) {
     println!(
-        "\t{}{}{}\n",
+        "\t{}Usage:{} {}\n",
         MFText::Bold,
-        &HELP_MESSAGE.header,
-        MFText::Reset
+        MFText::Reset,
+        match command.subcommands.is_empty() {
+            true => usage(path),
+            false => format!("{} <command>", command_name(path)),
+        }
     );
 
+    println!("\t{}\n", command.description);
+
+    if !command.aliases.is_empty() {
+        println!("\tAlso: {}\n", command.aliases.join(", "));
+    }
+
+    let subcommands: Vec<Row> = command
+        .subcommands
+        .iter()
+        .filter(|subcommand| !subcommand.hidden)
+        .map(|subcommand| {
+            (
+                join(subcommand.name, &subcommand.usage_args()),
+                subcommand.description,
+            )
+        })
+        .collect();
+
+    if !subcommands.is_empty() {
+        print_rows("Commands:", Some(&command_name(path)), &subcommands);
+    }
+
+    if !command.flags.is_empty() {
+        print_rows("Flags:", None, &flag_rows(command.flags));
+    }
+
+    print_rows("Global flags:", None, &flag_rows(GLOBAL_FLAGS));
+}
+
+/// 'add' + '<mod>' = 'add <mod>'
+fn join(name: &str, args: &str) -> String {
+    match args.is_empty() {
+        true => name.to_string(),
+        false => format!("{name} {args}"),
+    }
+}
+
+/// Rows of a flag table
+fn flag_rows(flags: &'static [FlagSpec]) -> Vec<Row> {
+    flags
+        .iter()
+        .map(|flag| (flag.usage(), flag.description))
+        .collect()
+}
+
+/// Prints a table in the MineFetch style
+fn print_rows(header: &str, prefix: Option<&str>, rows: &[Row]) {
+    // The amount of space that the largest 'name' takes
+    let space_size = find_the_largest_msg(rows);
+
+    // Print the header
+    println!("\t{}{}{}\n", MFText::Bold, header, MFText::Reset);
+
+    let prefix = prefix
+        .map(|prefix| format!("{prefix} "))
+        .unwrap_or_default();
+
     // Print all messages
-    for message in HELP_MESSAGE.message {
+    for (name, description) in rows {
         println!(
-            "\t{}{} {}{}{}\t{};",
+            "\t{}{}{}{}{}\t{};",
             MFText::Bold,
-            &HELP_MESSAGE.program_name, // program name
-            message.name,               // name of the command
+            prefix, // program name
+            name,   // name of the command
             MFText::Reset,
-            " ".repeat(space_size - message.name.len()), // indent
-            message.description                          // the description of the command
+            " ".repeat(space_size - name.len()), // indent
+            description                          // the description of the command
         );
 
         // footer
-        println!(
-            "\t{}",
-            "~".repeat(message.name.len() + HELP_MESSAGE.program_name.len() + 2)
-        );
+        println!("\t{}", "~".repeat(name.len() + prefix.len() + 1));
     }
-}
 
-/// Gets the largest name in the 'Help' structure
-pub fn find_the_largest_msg(helpmsg: &Help) -> usize {
-    // Create a mutable value
-    let mut lenght: usize = 0;
-
-    // Go through all messages
-    for message in helpmsg.message {
-        /*
-        If this name is larger than
-        previous one then rewrite the
-        length value with the new one
-        */
-        if message.name.len() > lenght {
-            lenght = message.name.len()
-        }
-    }
+    println!();
+}
 
-    // Return the result
-    return lenght;
+/// Gets the largest name in the table
+fn find_the_largest_msg(rows: &[Row]) -> usize {
+    rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0)
 }
@@ -0,0 +1,187 @@
+/*
+ _   _ _____ _____ ____
+| | | |_   _|_   _|  _ \
+| |_| | | |   | | | |_) |
+|  _  | | |   | | |  __/
+|_| |_| |_|   |_| |_|
+
+*/
+
+// Standard imports
+use std::result::Result;
+use std::time::Duration;
+
+// External crates
+use reqwest::header::HeaderMap;
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::time::sleep;
+
+// Internal modules
+use crate::consts::{
+    BACKOFF_BASE_MS, CONNECT_TIMEOUT_SECS, MAX_RETRIES, READ_TIMEOUT_SECS, USER_AGENT,
+};
+use crate::error::MineFetchError;
+use crate::utils::is_verbose;
+
+/// The error body that Modrinth sends with non-2xx responses
+#[derive(Deserialize)]
+struct ApiError {
+    error: String,
+    description: String,
+}
+
+/// Creates a Client with timeouts and the User-Agent set
+pub fn build_client() -> Result<Client, MineFetchError> {
+    let client = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .read_timeout(Duration::from_secs(READ_TIMEOUT_SECS))
+        .build()?;
+
+    Ok(client)
+}
+
+/// Sends a request, retries on transient failures and checks the status
+pub async fn send(request: RequestBuilder) -> Result<Response, MineFetchError> {
+    let mut attempt: u32 = 0;
+
+    loop {
+        // Every attempt needs its own copy of the request
+        let current = request.try_clone().ok_or_else(|| {
+            MineFetchError::Invalid("This request can't be sent more than once".into())
+        })?;
+
+        // Build it here, so --verbose can show what's sent
+        let (client, current) = current.build_split();
+        let current = current?;
+
+        if is_verbose() {
+            eprintln!(":dbg: {} {}", current.method(), current.url());
+        }
+
+        match client.execute(current).await {
+            Ok(response) => {
+                let status = response.status();
+
+                if is_verbose() {
+                    eprintln!(":dbg: {} {}", status, response.url());
+                }
+
+                // Pause before the next request if the limit has been used up
+                // (304 is an answer to If-None-Match, it's a success too)
+                if status.is_success() || status == StatusCode::NOT_MODIFIED {
+                    if remaining_requests(response.headers()) == Some(0) {
+                        wait_for_reset(response.headers(), attempt).await;
+                    }
+
+                    return Ok(response);
+                }
+
+                // Too many requests: wait until the limit resets and try again
+                if status == StatusCode::TOO_MANY_REQUESTS && attempt < MAX_RETRIES {
+                    wait_for_reset(response.headers(), attempt).await;
+                    attempt += 1;
+                    continue;
+                }
+
+                // Server errors are usually temporary
+                if status.is_server_error() && attempt < MAX_RETRIES {
+                    sleep(backoff(attempt)).await;
+                    attempt += 1;
+                    continue;
+                }
+
+                return Err(status_error(response).await);
+            }
+
+            // Timeouts and connection errors are worth another try
+            Err(error)
+                if (error.is_timeout() || error.is_connect() || error.is_request())
+                    && attempt < MAX_RETRIES =>
+            {
+                sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+/// Sends a GET request and parses the JSON response
+pub async fn get_json<T: DeserializeOwned>(client: &Client, url: Url) -> Result<T, MineFetchError> {
+    let response = send(client.get(url.clone())).await?.text().await?;
+
+    serde_json::from_str(&response)
+        .map_err(|error| MineFetchError::parse(format!("the response from {url}"), error))
+}
+
+/// Sends a POST request with a JSON body and parses the JSON response
+pub async fn post_json<B: Serialize, T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    body: &B,
+) -> Result<T, MineFetchError> {
+    let request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(body)?);
+
+    let response = send(request).await?.text().await?;
+
+    serde_json::from_str(&response)
+        .map_err(|error| MineFetchError::parse(format!("the response from {url}"), error))
+}
+
+/// Turns a non-successful response into an error with Modrinth's description
+async fn status_error(response: Response) -> MineFetchError {
+    let url = response.url().to_string();
+    let status = response.status().as_u16();
+    let text = response.text().await.unwrap_or_default();
+
+    // Use the description if Modrinth sent one, otherwise keep the raw body
+    let body = match serde_json::from_str::<ApiError>(&text) {
+        Ok(api_error) => format!("{} ({})", api_error.description, api_error.error),
+        Err(_) => text.trim().to_string(),
+    };
+
+    MineFetchError::Status { url, status, body }
+}
+
+/// Reads X-Ratelimit-Remaining
+fn remaining_requests(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("X-Ratelimit-Remaining")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Waits for X-Ratelimit-Reset seconds (or a backoff if there's no header)
+async fn wait_for_reset(headers: &HeaderMap, attempt: u32) {
+    let reset = headers
+        .get("X-Ratelimit-Reset")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+
+    let delay = match reset {
+        Some(seconds) => Duration::from_secs(seconds.max(1)),
+        None => backoff(attempt),
+    };
+
+    eprintln!(
+        ":wrn: Modrinth rate limit reached, waiting {} seconds",
+        delay.as_secs().max(1)
+    );
+
+    sleep(delay).await;
+}
+
+/// Exponential backoff: 0.5s, 1s, 2s...
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(BACKOFF_BASE_MS * 2u64.pow(attempt))
+}
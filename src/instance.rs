@@ -0,0 +1,193 @@
+/*
+ ___           _
+|_ _|_ __  ___| |_ __ _ _ __   ___ ___
+ | || '_ \/ __| __/ _` | '_ \ / __/ _ \
+ | || | | \__ \ || (_| | | | | (_|  __/
+|___|_| |_|___/\__\__,_|_| |_|\___\___|
+
+*/
+
+// Standard imports
+use std::path::{Path, PathBuf};
+
+// External crates
+use serde::Deserialize;
+
+// Internal modules
+use crate::error::MineFetchError;
+use crate::structs::Profile;
+use crate::utils::{get_datahome, get_homedir};
+
+/// What MineFetch needs from a Prism Launcher / MultiMC instance
+pub struct Instance {
+    pub name: String,
+    pub gameversion: String,
+    pub loader: String,
+    pub modsfolder: PathBuf,
+}
+
+/// mmc-pack.json
+#[derive(Deserialize)]
+struct Pack {
+    components: Vec<Component>,
+}
+
+/// A component of the instance (Minecraft itself, a loader, LWJGL...)
+#[derive(Deserialize)]
+struct Component {
+    uid: String,
+    version: Option<String>,
+}
+
+/// Component ids of the loaders (component id, Modrinth name)
+const LOADER_COMPONENTS: [(&str, &str); 4] = [
+    ("net.fabricmc.fabric-loader", "fabric"),
+    ("org.quiltmc.quilt-loader", "quilt"),
+    ("net.minecraftforge", "forge"),
+    ("net.neoforged", "neoforge"),
+];
+
+/// Reads instance.cfg and mmc-pack.json of the instance
+pub async fn read_instance(path: &Path) -> Result<Instance, MineFetchError> {
+    let cfg_path = path.join("instance.cfg");
+    let pack_path = path.join("mmc-pack.json");
+
+    let cfg = tokio::fs::read_to_string(&cfg_path)
+        .await
+        .map_err(|error| MineFetchError::io(&cfg_path, error))?;
+
+    // instance.cfg is an INI file, only 'name' is needed
+    let name = cfg
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "name")
+        .map(|(_, value)| value.trim().to_string())
+        .unwrap_or_else(|| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+    let pack = tokio::fs::read_to_string(&pack_path)
+        .await
+        .map_err(|error| MineFetchError::io(&pack_path, error))?;
+
+    let pack: Pack = serde_json::from_str(&pack)
+        .map_err(|error| MineFetchError::parse(pack_path.display().to_string(), error))?;
+
+    let (gameversion, loader) = components(&pack);
+
+    let gameversion = gameversion.ok_or_else(|| {
+        MineFetchError::parse(
+            pack_path.display().to_string(),
+            "there's no net.minecraft component",
+        )
+    })?;
+
+    let loader = loader.ok_or_else(|| {
+        MineFetchError::Invalid(format!(
+            "The instance {name} has no mod loader (Fabric, Quilt, Forge or NeoForge)"
+        ))
+    })?;
+
+    Ok(Instance {
+        name,
+        gameversion,
+        loader,
+        modsfolder: game_dir(path).join("mods"),
+    })
+}
+
+/// Returns the Minecraft version and the loader from the components
+fn components(pack: &Pack) -> (Option<String>, Option<String>) {
+    let gameversion = pack
+        .components
+        .iter()
+        .find(|component| component.uid == "net.minecraft")
+        .and_then(|component| component.version.clone());
+
+    let loader = pack.components.iter().find_map(|component| {
+        LOADER_COMPONENTS
+            .iter()
+            .find(|(uid, _)| *uid == component.uid)
+            .map(|(_, loader)| loader.to_string())
+    });
+
+    (gameversion, loader)
+}
+
+/// Newer Prism Launcher uses 'minecraft', older versions and MultiMC use '.minecraft'
+fn game_dir(path: &Path) -> PathBuf {
+    let visible = path.join("minecraft");
+
+    if visible.is_dir() {
+        visible
+    } else {
+        path.join(".minecraft")
+    }
+}
+
+/// Finds instances in the default Prism Launcher and MultiMC data directories
+pub async fn find_instances() -> Result<Vec<PathBuf>, MineFetchError> {
+    let datahome = get_datahome().await?;
+    let homedir = get_homedir().await?;
+
+    let roots = [
+        datahome.join("PrismLauncher").join("instances"),
+        datahome.join("multimc").join("instances"),
+        datahome.join("MultiMC").join("instances"),
+        homedir
+            .join(".var/app/org.prismlauncher.PrismLauncher/data/PrismLauncher")
+            .join("instances"),
+    ];
+
+    let mut instances: Vec<PathBuf> = Vec::new();
+
+    for root in roots {
+        let Ok(mut dir) = tokio::fs::read_dir(&root).await else {
+            continue;
+        };
+
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+
+            if path.join("instance.cfg").is_file() && path.join("mmc-pack.json").is_file() {
+                instances.push(path);
+            }
+        }
+    }
+
+    instances.sort();
+
+    Ok(instances)
+}
+
+/// Warns if the instance's components don't match the profile anymore
+pub async fn check_instance(profile: &Profile) {
+    let Some(path) = &profile.instance else {
+        return;
+    };
+
+    let instance = match read_instance(Path::new(path)).await {
+        Ok(instance) => instance,
+        Err(error) => {
+            eprintln!(
+                ":wrn: Can't check the instance of {}: {error}",
+                profile.name
+            );
+            return;
+        }
+    };
+
+    if instance.gameversion != profile.gameversion || instance.loader != profile.loader {
+        eprintln!(
+            ":wrn: The instance {} now uses {} {}, but the profile {} has {} {} (fix it with minefetch profile edit)",
+            instance.name,
+            instance.loader,
+            instance.gameversion,
+            profile.name,
+            profile.loader,
+            profile.gameversion
+        );
+    }
+}
@@ -0,0 +1,113 @@
+/*
+     _
+    | | __ _ _ __ ___
+ _  | |/ _` | '__/ __|
+| |_| | (_| | |  \__ \
+ \___/ \__,_|_|  |___/
+
+*/
+
+// Standard imports
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// External crates
+use serde::Deserialize;
+use zip::ZipArchive;
+
+/// What a mod says about itself inside its jar
+pub struct JarMetadata {
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+}
+
+/// fabric.mod.json
+#[derive(Deserialize)]
+struct FabricMod {
+    id: String,
+    name: Option<String>,
+    version: Option<String>,
+}
+
+/// quilt.mod.json
+#[derive(Deserialize)]
+struct QuiltMod {
+    quilt_loader: QuiltLoader,
+}
+
+#[derive(Deserialize)]
+struct QuiltLoader {
+    id: String,
+    version: Option<String>,
+    metadata: Option<QuiltMetadata>,
+}
+
+#[derive(Deserialize)]
+struct QuiltMetadata {
+    name: Option<String>,
+}
+
+/// META-INF/mods.toml and META-INF/neoforge.mods.toml
+#[derive(Deserialize)]
+struct ForgeMods {
+    mods: Vec<ForgeMod>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgeMod {
+    mod_id: String,
+    display_name: Option<String>,
+    version: Option<String>,
+}
+
+/// Reads the mod's metadata from the jar (Fabric, Quilt, Forge and NeoForge)
+pub fn read_metadata(path: &Path) -> Option<JarMetadata> {
+    let mut archive = ZipArchive::new(File::open(path).ok()?).ok()?;
+
+    if let Some(text) = read_entry(&mut archive, "fabric.mod.json") {
+        let parsed: FabricMod = serde_json::from_str(&text).ok()?;
+        return Some(JarMetadata {
+            id: parsed.id,
+            name: parsed.name,
+            version: parsed.version,
+        });
+    }
+
+    if let Some(text) = read_entry(&mut archive, "quilt.mod.json") {
+        let parsed: QuiltMod = serde_json::from_str(&text).ok()?;
+        return Some(JarMetadata {
+            id: parsed.quilt_loader.id,
+            name: parsed
+                .quilt_loader
+                .metadata
+                .and_then(|metadata| metadata.name),
+            version: parsed.quilt_loader.version,
+        });
+    }
+
+    for entry in ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"] {
+        if let Some(text) = read_entry(&mut archive, entry) {
+            let parsed: ForgeMods = toml::from_str(&text).ok()?;
+            let first = parsed.mods.into_iter().next()?;
+            return Some(JarMetadata {
+                id: first.mod_id,
+                name: first.display_name,
+                // '${file.jarVersion}' is filled in at build time, it's useless here
+                version: first.version.filter(|version| !version.starts_with("${")),
+            });
+        }
+    }
+
+    None
+}
+
+/// Reads one file from the archive as a string
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut text = String::new();
+    entry.read_to_string(&mut text).ok()?;
+    Some(text)
+}
@@ -0,0 +1,351 @@
+/*
+ _                    _
+| |    ___   __ _  __| | ___ _ __
+| |   / _ \ / _` |/ _` |/ _ \ '__|
+| |__| (_) | (_| | (_| |  __/ |
+|_____\___/ \__,_|\__,_|\___|_|
+
+*/
+
+// Standard imports
+use std::path::Path;
+
+// External crates
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncReadExt;
+
+// Internal modules
+use crate::api::Anymod;
+use crate::consts::{FABRIC_META, NEOFORGE_MAVEN, QUILT_META};
+use crate::downloader::download_into;
+use crate::error::MineFetchError;
+use crate::http::{get_json, send};
+use crate::structs::{Profile, Settings};
+
+/// A server loader build that can be downloaded
+pub struct LoaderRelease {
+    pub version: String,
+    pub url: String,
+
+    /// The name of the jar in the instance root
+    pub filename: String,
+
+    /// What to run after downloading (installers only)
+    pub next_step: Option<String>,
+
+    /// SHA-1 from the Maven repository (Fabric's launcher is built on request, it has none)
+    pub sha1: Option<String>,
+}
+
+/// An entry of Fabric's and Quilt's loader lists
+#[derive(Deserialize)]
+struct LoaderEntry {
+    loader: MetaVersion,
+}
+
+/// A loader or installer version in Fabric / Quilt meta
+#[derive(Deserialize)]
+struct MetaVersion {
+    version: String,
+
+    /// Quilt doesn't have this, its betas have '-beta' in the version
+    stable: Option<bool>,
+
+    /// Only installers have a URL
+    url: Option<String>,
+}
+
+impl MetaVersion {
+    fn is_stable(&self) -> bool {
+        self.stable.unwrap_or(!self.version.contains('-'))
+    }
+}
+
+/// NeoForge's Maven versions list
+#[derive(Deserialize)]
+struct MavenVersions {
+    versions: Vec<String>,
+}
+
+/// Finds the loader build for the profile's Minecraft version (the newest stable one if not given)
+pub async fn find_release(
+    client: &Client,
+    profile: &Profile,
+    settings: &Settings,
+    version: Option<&str>,
+) -> Result<LoaderRelease, MineFetchError> {
+    // The service can be replaced with a local stand-in
+    let base = |default: &str| -> String {
+        settings
+            .loader_meta
+            .get(&profile.loader)
+            .map(String::as_str)
+            .unwrap_or(default)
+            .trim_end_matches('/')
+            .to_string()
+    };
+
+    match profile.loader.as_str() {
+        "fabric" => fabric(client, &base(FABRIC_META), &profile.gameversion, version).await,
+        "quilt" => quilt(client, &base(QUILT_META), &profile.gameversion, version).await,
+        "neoforge" => neoforge(client, &base(NEOFORGE_MAVEN), &profile.gameversion, version).await,
+        "forge" => Err(MineFetchError::Invalid(
+            "Forge servers can't be installed by MineFetch, get the installer from files.minecraftforge.net".into(),
+        )),
+        loader => Err(MineFetchError::Invalid(format!("Unknown loader: {loader}"))),
+    }
+}
+
+/// Picks the given version or the newest stable one (the lists are newest first)
+fn pick_version(
+    versions: Vec<MetaVersion>,
+    wanted: Option<&str>,
+    what: &str,
+) -> Result<MetaVersion, MineFetchError> {
+    let found = match wanted {
+        Some(wanted) => versions
+            .into_iter()
+            .find(|version| version.version == wanted),
+        None => {
+            let stable = versions
+                .iter()
+                .position(MetaVersion::is_stable)
+                .unwrap_or(0);
+            versions.into_iter().nth(stable)
+        }
+    };
+
+    found.ok_or_else(|| match wanted {
+        Some(wanted) => {
+            MineFetchError::NotFound(format!("There's no version {wanted} of the {what}"))
+        }
+        None => MineFetchError::NotFound(format!("There's no {what}")),
+    })
+}
+
+/// Fabric has a ready server launcher for every loader and installer pair
+async fn fabric(
+    client: &Client,
+    meta: &str,
+    gameversion: &str,
+    version: Option<&str>,
+) -> Result<LoaderRelease, MineFetchError> {
+    let loaders: Vec<LoaderEntry> = get_meta(
+        client,
+        &format!("{meta}/v2/versions/loader/{gameversion}"),
+        gameversion,
+    )
+    .await?;
+
+    let loader = pick_version(
+        loaders.into_iter().map(|entry| entry.loader).collect(),
+        version,
+        &format!("Fabric loader for Minecraft {gameversion}"),
+    )?;
+
+    let installers: Vec<MetaVersion> = get_meta(
+        client,
+        &format!("{meta}/v2/versions/installer"),
+        gameversion,
+    )
+    .await?;
+    let installer = pick_version(installers, None, "Fabric installer")?;
+
+    Ok(LoaderRelease {
+        url: format!(
+            "{meta}/v2/versions/loader/{gameversion}/{}/{}/server/jar",
+            loader.version, installer.version
+        ),
+        version: loader.version,
+        filename: "fabric-server-launcher.jar".into(),
+        next_step: None,
+        sha1: None,
+    })
+}
+
+/// Quilt only has an installer, it sets the server up itself
+async fn quilt(
+    client: &Client,
+    meta: &str,
+    gameversion: &str,
+    version: Option<&str>,
+) -> Result<LoaderRelease, MineFetchError> {
+    let loaders: Vec<LoaderEntry> = get_meta(
+        client,
+        &format!("{meta}/v3/versions/loader/{gameversion}"),
+        gameversion,
+    )
+    .await?;
+
+    let loader = pick_version(
+        loaders.into_iter().map(|entry| entry.loader).collect(),
+        version,
+        &format!("Quilt loader for Minecraft {gameversion}"),
+    )?;
+
+    let installers: Vec<MetaVersion> = get_meta(
+        client,
+        &format!("{meta}/v3/versions/installer"),
+        gameversion,
+    )
+    .await?;
+    let installer = pick_version(installers, None, "Quilt installer")?;
+
+    let url = installer.url.ok_or_else(|| {
+        MineFetchError::parse("the Quilt installer list", "the installer has no URL")
+    })?;
+
+    let sha1 = get_sha1(client, &url).await?;
+
+    Ok(LoaderRelease {
+        next_step: Some(format!(
+            "java -jar quilt-installer.jar install server {gameversion} {} --download-server",
+            loader.version
+        )),
+        version: loader.version,
+        url,
+        filename: "quilt-installer.jar".into(),
+        sha1: Some(sha1),
+    })
+}
+
+/// NeoForge versions follow Minecraft's: 1.21.1 -> 21.1.x, 1.21 -> 21.0.x
+async fn neoforge(
+    client: &Client,
+    maven: &str,
+    gameversion: &str,
+    version: Option<&str>,
+) -> Result<LoaderRelease, MineFetchError> {
+    let list: MavenVersions = get_meta(
+        client,
+        &format!("{maven}/api/maven/versions/releases/net/neoforged/neoforge"),
+        gameversion,
+    )
+    .await?;
+
+    let short = gameversion.strip_prefix("1.").unwrap_or(gameversion);
+    let prefix = match short.split_once('.') {
+        Some((major, minor)) => format!("{major}.{minor}."),
+        None => format!("{short}.0."),
+    };
+
+    // The list is oldest first
+    let matching: Vec<&String> = list
+        .versions
+        .iter()
+        .filter(|candidate| candidate.starts_with(&prefix))
+        .collect();
+
+    let found = match version {
+        Some(wanted) => matching.into_iter().find(|candidate| *candidate == wanted),
+        None => matching
+            .iter()
+            .rev()
+            .find(|candidate| !candidate.contains("beta"))
+            .or(matching.last())
+            .copied(),
+    };
+
+    let found = found.cloned().ok_or_else(|| {
+        MineFetchError::NotFound(format!(
+            "There's no NeoForge {}for Minecraft {gameversion}",
+            version
+                .map(|version| format!("{version} "))
+                .unwrap_or_default()
+        ))
+    })?;
+
+    let url =
+        format!("{maven}/releases/net/neoforged/neoforge/{found}/neoforge-{found}-installer.jar");
+    let sha1 = get_sha1(client, &url).await?;
+
+    Ok(LoaderRelease {
+        url,
+        version: found,
+        filename: "neoforge-installer.jar".into(),
+        next_step: Some("java -jar neoforge-installer.jar --installServer".into()),
+        sha1: Some(sha1),
+    })
+}
+
+/// Fetches a list from the metadata service, an unknown game version is NotFound
+async fn get_meta<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    gameversion: &str,
+) -> Result<T, MineFetchError> {
+    let url = Url::parse(url)?;
+
+    match get_json(client, url).await {
+        Err(MineFetchError::Status {
+            status: 400 | 404, ..
+        }) => Err(MineFetchError::NotFound(format!(
+            "The loader doesn't support Minecraft {gameversion}"
+        ))),
+        result => result,
+    }
+}
+
+/// Reads the .sha1 file that Maven keeps next to every artifact
+async fn get_sha1(client: &Client, url: &str) -> Result<String, MineFetchError> {
+    let sha1_url = format!("{url}.sha1");
+    let text = send(client.get(&sha1_url)).await?.text().await?;
+
+    // Some repositories add the file name after the hash
+    let sha1 = text.split_whitespace().next().unwrap_or_default();
+
+    if sha1.len() != 40 || !sha1.chars().all(|char| char.is_ascii_hexdigit()) {
+        return Err(MineFetchError::parse(sha1_url, "it isn't a SHA-1 hash"));
+    }
+
+    Ok(sha1.to_ascii_lowercase())
+}
+
+/// Downloads the jar into the given directory (resumable and checked like mods)
+pub async fn download_release(
+    client: &Client,
+    release: &LoaderRelease,
+    dir: &Path,
+) -> Result<(), MineFetchError> {
+    let file = Anymod {
+        title: Some(release.filename.clone()),
+        project_id: String::new(),
+        version_name: release.version.clone(),
+        version_id: String::new(),
+        filename: release.filename.clone(),
+        hash: release.sha1.clone().unwrap_or_default(),
+        url: release.url.clone(),
+        size: None,
+        depends: None,
+    };
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{wide_msg} {bar:50} {percent}%").expect("valid template"),
+    );
+    bar.set_message(release.filename.clone());
+
+    download_into(&file, client, dir, bar, ProgressBar::hidden()).await?;
+
+    // Make sure it's a jar and not an error page (Fabric's launcher has no hash to check)
+    let path = dir.join(&release.filename);
+    let mut magic = [0u8; 4];
+
+    let is_jar = match tokio::fs::File::open(&path).await {
+        Ok(mut jar) => jar.read_exact(&mut magic).await.is_ok() && magic == *b"PK\x03\x04",
+        Err(error) => return Err(MineFetchError::io(&path, error)),
+    };
+
+    if !is_jar {
+        let _ = tokio::fs::remove_file(&path).await;
+        return Err(MineFetchError::Corrupted(format!(
+            "{} from {} isn't a jar",
+            release.filename, release.url
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+/*
+ _               _     __ _ _
+| |    ___   ___| | __/ _(_) | ___
+| |   / _ \ / __| |/ / |_| | |/ _ \
+| |__| (_) | (__|   <|  _| | |  __/
+|_____\___/ \___|_|\_\_| |_|_|\___|
+
+*/
+
+// Standard imports
+use std::path::{Path, PathBuf};
+
+// External crates
+use serde::{Deserialize, Serialize};
+
+// Internal modules
+use crate::api::Anymod;
+use crate::error::MineFetchError;
+use crate::migrate::{LOCKFILE_SCHEMA_VERSION, Schema, load};
+use crate::utils::{atomic_write, find_in_ancestors};
+
+/// The name of the lockfile
+pub const LOCKFILE_NAME: &str = "minefetch.lock";
+
+/// minefetch.lock
+#[derive(Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Format version of the file (see migrate.rs)
+    #[serde(default)]
+    pub schema_version: u32,
+
+    /// What the mods were installed for
+    pub gameversion: String,
+    pub loader: String,
+
+    #[serde(default, rename = "mod")]
+    pub mods: Vec<LockedMod>,
+}
+
+/// One exact file
+#[derive(Serialize, Deserialize)]
+pub struct LockedMod {
+    pub title: Option<String>,
+    pub project_id: String,
+    pub version_id: String,
+    pub version_name: String,
+    pub filename: String,
+    pub url: String,
+    pub size: Option<u64>,
+    pub hashes: LockedHashes,
+}
+
+/// Hashes of the file
+#[derive(Serialize, Deserialize)]
+pub struct LockedHashes {
+    pub sha1: String,
+}
+
+impl From<&Anymod> for LockedMod {
+    fn from(anymod: &Anymod) -> Self {
+        LockedMod {
+            title: anymod.title.clone(),
+            project_id: anymod.project_id.clone(),
+            version_id: anymod.version_id.clone(),
+            version_name: anymod.version_name.clone(),
+            filename: anymod.filename.clone(),
+            url: anymod.url.clone(),
+            size: anymod.size,
+            hashes: LockedHashes {
+                sha1: anymod.hash.clone(),
+            },
+        }
+    }
+}
+
+impl LockedMod {
+    /// Turns the entry back into something the downloader understands
+    pub fn to_anymod(&self) -> Anymod {
+        Anymod {
+            title: self.title.clone(),
+            project_id: self.project_id.clone(),
+            version_name: self.version_name.clone(),
+            version_id: self.version_id.clone(),
+            filename: self.filename.clone(),
+            hash: self.hashes.sha1.clone(),
+            url: self.url.clone(),
+            size: self.size,
+            depends: None,
+        }
+    }
+}
+
+impl Lockfile {
+    /// Creates a lockfile from installed mods
+    pub fn new(gameversion: &str, loader: &str, mods: &[Anymod]) -> Self {
+        Lockfile {
+            schema_version: LOCKFILE_SCHEMA_VERSION,
+            gameversion: gameversion.to_string(),
+            loader: loader.to_string(),
+            mods: mods.iter().map(LockedMod::from).collect(),
+        }
+    }
+}
+
+/// Returns the given lockfile or looks for minefetch.lock in the current directory and its parents
+pub fn find_lockfile(path: Option<&str>) -> Result<PathBuf, MineFetchError> {
+    if let Some(path) = path {
+        return Ok(PathBuf::from(path));
+    }
+
+    find_in_ancestors(LOCKFILE_NAME)?.ok_or_else(|| {
+        MineFetchError::NotFound(format!(
+            "There's no {LOCKFILE_NAME} in this directory or its parents (create it with minefetch lock generate)"
+        ))
+    })
+}
+
+/// Reads minefetch.lock
+pub async fn read_lockfile(path: &Path) -> Result<Lockfile, MineFetchError> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|error| MineFetchError::io(path, error))?;
+
+    load(path, &contents, Schema::Lockfile).await
+}
+
+/// Writes minefetch.lock
+pub async fn write_lockfile(path: &Path, lockfile: &Lockfile) -> Result<(), MineFetchError> {
+    let contents = format!(
+        "# Generated by 'minefetch lock generate', install it with 'minefetch install --locked'\n\n{}",
+        toml::to_string(lockfile)?
+    );
+
+    atomic_write(path, contents).await
+}
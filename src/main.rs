@@ -8,131 +8,188 @@
 */
 
 // Standard imports
+use std::process::ExitCode;
 use std::result::Result;
 
 // Internal modules
 mod api;
 mod cache;
+mod cli;
+mod completion;
 mod consts;
 mod downloader;
+mod error;
+mod filelock;
 mod front;
 mod helpmsg;
+mod http;
+mod instance;
+mod jar;
+mod loader;
+mod lockfile;
+mod manifest;
+mod marker;
+mod metacache;
 mod mfio;
+mod migrate;
 mod profile;
 mod structs;
 mod utils;
 
+use crate::cli::{Command, parse_args};
+use crate::completion::{complete, print_script};
 use crate::consts::{NAME, PROGRAM_VERSION, USER_AGENT};
+use crate::error::{EXIT_UPDATES_AVAILABLE, MineFetchError};
 use crate::front::*;
 use crate::helpmsg::display_help;
 
 #[tokio::main]
 // The start of the main function
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> ExitCode {
     // Calling initialise to start the program
     match initialise().await {
-        Ok(_) => (),
-        Err(error) => eprintln!(":err: {error}"),
-    };
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!(":err: {error}");
 
-    // Exit
-    Ok(())
+            // Every error category has its own exit code (see error.rs)
+            ExitCode::from(error.exit_code())
+        }
+    }
 }
 
 /// The start of the main async function
-async fn initialise() -> Result<(), Box<dyn std::error::Error>> {
-    // Read the commandline arguments
-    let args: Vec<String> = std::env::args().collect();
+async fn initialise() -> Result<ExitCode, MineFetchError> {
+    // Read the commandline arguments (without the program name)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, options) = parse_args(&args)?;
+
+    // Only some commands know how to run for every profile
+    if options.all_profiles
+        && !matches!(
+            command,
+            Command::Update { .. } | Command::Outdated | Command::List
+        )
+    {
+        return Err(MineFetchError::Invalid(
+            "--all-profiles works only with update, outdated and list".into(),
+        ));
+    }
+
+    match command {
+        // minefetch [%command%...] --help
+        Command::Help(path) => display_help(&path).await,
 
-    // Get the first argument
-    match args.get(1).map(String::as_str) {
         // minefetch add %mod_id_or_slug%
-        Some("add") => match args.get(2).map(String::as_str) // Get the mod id / slug
-        {
-            Some(modname) => {
-                add_mod(modname).await?;
-            }
+        Command::Add(modname) => add_mod(&modname, &options).await?,
 
-            // If the prompt is empty
-            _ => display_help().await,
-        },
+        // minefetch info %mod_id_or_slug%
+        Command::Info(modname) => info(&modname, &options).await?,
 
-        // minefetch profile %subcommand%
-        Some("profile") => match args.get(2).map(String::as_str) {
-            // minefetch profile create
-            Some("create") => create_profile().await?,
+        // minefetch search [%query%] [--limit ..] [--sort ..] ...
+        Command::Search(query) => search(query, &options).await?,
 
-            // minefetch profile delete (all or only one)
-            Some("delete") => match args.get(3).map(String::as_str) {
-                // minefetch profile delete all
-                Some("all") => delete_profile(1).await?,
+        // minefetch profile create
+        Command::ProfileCreate => create_profile().await?,
 
-                // minefetch profile delete
-                _ => delete_profile(0).await?,
-            },
+        // minefetch profile delete [all]
+        Command::ProfileDelete { all } => delete_profile(all as u32).await?,
 
-            // minefetch profile switch
-            Some("switch") => switch_profile().await?,
+        // minefetch profile switch
+        Command::ProfileSwitch => switch_profile().await?,
 
-            // minefetch profile list
-            Some("list") => list_profiles().await?,
+        // minefetch profile list
+        Command::ProfileList => list_profiles().await?,
 
-            // If the prompt is empty
-            _ => display_help().await,
-        },
+        // minefetch profile import-instance [%path%]
+        Command::ProfileImport(path) => import_instance(path.as_deref()).await?,
+
+        // minefetch profile clone %name% --dir %path% [--name ..] [--game-version ..]
+        Command::ProfileClone(clone) => clone_profile(clone, &options).await?,
+
+        // minefetch profile edit [--name ..] [--dir ..] [--game-version ..] [--loader ..]
+        Command::ProfileEdit(edit) => edit_profile(edit, &options).await?,
 
         // minefetch version
-        Some("version") => println!(":out: {} {}", NAME, PROGRAM_VERSION),
+        Command::Version => println!(":out: {} {}", NAME, PROGRAM_VERSION),
+
+        // minefetch update [%mod%...] [--exclude %mod%] OR minefetch upgrade
+        Command::Update { include, exclude } => {
+            if options.all_profiles {
+                let command = ProfileCommand::Update {
+                    include: &include,
+                    exclude: &exclude,
+                };
+                for_all_profiles(command, &options).await?;
+            } else {
+                upgrade(&include, &exclude, &options).await?
+            }
+        }
+
+        // minefetch outdated (exits with a special code if there are updates)
+        Command::Outdated => {
+            let available = if options.all_profiles {
+                for_all_profiles(ProfileCommand::Outdated, &options).await?
+            } else {
+                outdated(&options).await?
+            };
+
+            if available {
+                return Ok(ExitCode::from(EXIT_UPDATES_AVAILABLE));
+            }
+        }
 
-        // minefetch search %query%
-        Some("search") => match args.get(2) {
-            /*
-                Doesn't use the value from args because
-                the program joins all the strings starting
-                from the third argument
-            */
-            Some(_) => search(args).await?,
+        // minefetch sync [--manifest %path%] [--side %side%] [--dry-run]
+        Command::Sync(sync_args) => sync(sync_args, &options).await?,
 
-            // Display the help message if empty
-            None => display_help().await,
-        },
+        // minefetch install --locked [--lockfile %path%]
+        Command::Install { lockfile } => install_locked(lockfile.as_deref(), &options).await?,
 
-        // minefetch update OR minefetch upgrade
-        Some("upgrade") | Some("update") => upgrade().await?,
+        // minefetch changelog [%mod%]
+        Command::Changelog(modname) => changelog(modname.as_deref(), &options).await?,
 
         // minefetch list
-        Some("list") => list_cached().await?,
+        Command::List if options.all_profiles => {
+            for_all_profiles(ProfileCommand::List, &options).await?;
+        }
+        Command::List => list_cached(&options).await?,
 
-        // minefetch lock %subcommand%
-        Some("lock") => match args.get(2).map(String::as_str) {
-            // minefetch lock add
-            Some("add") => {
-                fadd_lock().await?;
-            }
+        // minefetch lock add
+        Command::LockAdd => fadd_lock(&options).await?,
+
+        // minefetch lock remove
+        Command::LockRemove => rm_lock(&options).await?,
+
+        // minefetch lock list
+        Command::LockList => ls_lock(&options).await?,
 
-            // minefetch lock remove
-            Some("remove") => rm_lock().await?,
+        // minefetch lock generate [--output %path%]
+        Command::LockGenerate { output } => generate_lockfile(output.as_deref(), &options).await?,
 
-            // minefetch lock list
-            Some("list") => ls_lock().await?,
+        // minefetch loader install [--version %version%]
+        Command::LoaderInstall { version } => {
+            install_loader(version.as_deref(), false, &options).await?
+        }
 
-            // Display the help message in other cases
-            _ => display_help().await,
-        },
+        // minefetch loader update
+        Command::LoaderUpdate => install_loader(None, true, &options).await?,
 
         // minefetch edit
-        Some("edit") => fedit_mod().await?,
+        Command::Edit => fedit_mod(&options).await?,
 
-        // minefetch debug
-        Some("debug") => println!(":dbg: {} / {} / {}", NAME, PROGRAM_VERSION, USER_AGENT),
+        // minefetch completions %shell%
+        Command::Completions(shell) => print_script(&shell),
+
+        // minefetch __complete -- %words%... (called by the scripts)
+        Command::Complete(words) => complete(&words).await,
 
-        // Display a help message in other case
-        Some(_) => display_help().await,
+        // minefetch debug
+        Command::Debug => println!(":dbg: {} / {} / {}", NAME, PROGRAM_VERSION, USER_AGENT),
 
         // If arguments are empty
-        None => println!(":out: No arguments provided"),
+        Command::Nothing => println!(":out: No arguments provided (see minefetch --help)"),
     }
 
     // Success
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,200 @@
+/*
+ __  __             _  __           _
+|  \/  | __ _ _ __ (_)/ _| ___  ___| |_
+| |\/| |/ _` | '_ \| | |_ / _ \/ __| __|
+| |  | | (_| | | | | |  _|  __/\__ \ |_
+|_|  |_|\__,_|_| |_|_|_|  \___||___/\__|
+
+*/
+
+// Standard imports
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// External crates
+use serde::Deserialize;
+
+// Internal modules
+use crate::error::MineFetchError;
+use crate::structs::Version;
+use crate::utils::find_in_ancestors;
+
+/// The name of the manifest file
+pub const MANIFEST_NAME: &str = "minefetch.toml";
+
+/// minefetch.toml
+#[derive(Deserialize)]
+pub struct Manifest {
+    /// Mods by slug (or project id)
+    #[serde(default)]
+    pub mods: BTreeMap<String, ManifestEntry>,
+}
+
+/// Either a version constraint or a table with more options
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ManifestEntry {
+    Version(String),
+    Table(ManifestMod),
+}
+
+/// A mod in the manifest
+#[derive(Deserialize, Default, Clone)]
+pub struct ManifestMod {
+    /// Version constraint like "0.6.*" or ">=1.8, <1.9"
+    pub version: Option<String>,
+
+    /// The least stable channel that's allowed (release, beta or alpha)
+    pub channel: Option<String>,
+
+    /// Where the mod is needed (client, server or both)
+    pub side: Option<String>,
+}
+
+impl ManifestEntry {
+    /// Turns both forms into ManifestMod
+    pub fn to_mod(&self) -> ManifestMod {
+        match self {
+            ManifestEntry::Version(version) => ManifestMod {
+                version: Some(version.clone()),
+                ..ManifestMod::default()
+            },
+            ManifestEntry::Table(table) => table.clone(),
+        }
+    }
+}
+
+impl ManifestMod {
+    /// True if the version fits the constraint and the channel
+    pub fn matches(&self, version: &Version) -> bool {
+        let channel_ok = match self.channel.as_deref() {
+            Some("release") => version.version_type == "release",
+            Some("beta") => version.version_type != "alpha",
+            _ => true,
+        };
+
+        let version_ok = match &self.version {
+            Some(constraint) => matches_constraint(constraint, &version.version_number),
+            None => true,
+        };
+
+        channel_ok && version_ok
+    }
+
+    /// True if the mod is needed on this side ('both' and no side match everything)
+    pub fn is_for_side(&self, side: Option<&str>) -> bool {
+        match (self.side.as_deref(), side) {
+            (Some(mod_side), Some(side)) => mod_side == "both" || mod_side == side,
+            _ => true,
+        }
+    }
+}
+
+/// Looks for minefetch.toml in the current directory and its parents
+pub async fn find_manifest() -> Result<PathBuf, MineFetchError> {
+    find_in_ancestors(MANIFEST_NAME)?.ok_or_else(|| {
+        MineFetchError::NotFound(format!(
+            "There's no {MANIFEST_NAME} in this directory or its parents"
+        ))
+    })
+}
+
+/// Reads and checks the manifest
+pub async fn read_manifest(path: &Path) -> Result<Manifest, MineFetchError> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|error| MineFetchError::io(path, error))?;
+
+    let manifest: Manifest = toml::from_str(&contents)
+        .map_err(|error| MineFetchError::parse(path.display().to_string(), error))?;
+
+    // Catch typos before anything is downloaded
+    for (slug, entry) in &manifest.mods {
+        let entry = entry.to_mod();
+
+        if let Some(channel) = &entry.channel
+            && !["release", "beta", "alpha"].contains(&channel.as_str())
+        {
+            return Err(MineFetchError::Invalid(format!(
+                "{slug}: unknown channel {channel} (use release, beta or alpha)"
+            )));
+        }
+
+        if let Some(side) = &entry.side
+            && !["client", "server", "both"].contains(&side.as_str())
+        {
+            return Err(MineFetchError::Invalid(format!(
+                "{slug}: unknown side {side} (use client, server or both)"
+            )));
+        }
+
+        if let Some(constraint) = &entry.version
+            && constraint.split(',').any(|clause| clause.trim().is_empty())
+        {
+            return Err(MineFetchError::Invalid(format!(
+                "{slug}: empty version constraint"
+            )));
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Checks a version against constraints like "*", "0.6.*", "1.2.3" or ">=1.8, <1.9"
+fn matches_constraint(constraint: &str, version: &str) -> bool {
+    constraint.split(',').all(|clause| {
+        let clause = clause.trim();
+
+        // The longest operators go first
+        let (operator, wanted) = [">=", "<=", ">", "<", "="]
+            .iter()
+            .find_map(|operator| {
+                clause
+                    .strip_prefix(operator)
+                    .map(|rest| (*operator, rest.trim()))
+            })
+            .unwrap_or(("=", clause));
+
+        // "0.6.*" matches everything that starts with "0.6."
+        if let Some(prefix) = wanted.strip_suffix('*') {
+            return operator == "=" && version.starts_with(prefix);
+        }
+
+        let ordering = compare_versions(version, wanted);
+
+        match operator {
+            ">=" => ordering != Ordering::Less,
+            "<=" => ordering != Ordering::Greater,
+            ">" => ordering == Ordering::Greater,
+            "<" => ordering == Ordering::Less,
+            _ => ordering == Ordering::Equal,
+        }
+    })
+}
+
+/// Compares versions part by part, numbers as numbers ("1.10" > "1.9")
+fn compare_versions(left: &str, right: &str) -> Ordering {
+    let split = |version: &str| -> Vec<String> {
+        version
+            .split(['.', '-', '+', '_'])
+            .map(str::to_string)
+            .collect()
+    };
+
+    let left = split(left);
+    let right = split(right);
+
+    for (left, right) in left.iter().zip(&right) {
+        let ordering = match (left.parse::<u64>(), right.parse::<u64>()) {
+            (Ok(left), Ok(right)) => left.cmp(&right),
+            _ => left.cmp(right),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    left.len().cmp(&right.len())
+}
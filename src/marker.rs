@@ -0,0 +1,93 @@
+/*
+ __  __            _
+|  \/  | __ _ _ __| | _____ _ __
+| |\/| |/ _` | '__| |/ / _ \ '__|
+| |  | | (_| | |  |   <  __/ |
+|_|  |_|\__,_|_|  |_|\_\___|_|
+
+*/
+
+// Standard imports
+use std::path::{Path, PathBuf};
+
+// External crates
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+
+// Internal modules
+use crate::error::MineFetchError;
+use crate::structs::Profile;
+
+/// The name of the directory-local profile file
+pub const MARKER_NAME: &str = ".minefetch.toml";
+
+/// .minefetch.toml
+#[derive(Deserialize)]
+pub struct Marker {
+    pub profile: MarkerProfile,
+}
+
+/// Either a name of a profile from config.toml or a whole profile
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum MarkerProfile {
+    Name(String),
+    Inline(InlineProfile),
+}
+
+/// A profile defined right in .minefetch.toml
+#[derive(Deserialize)]
+pub struct InlineProfile {
+    pub name: String,
+
+    /// Relative paths start from the marker's directory
+    pub modsfolder: String,
+    pub gameversion: String,
+    pub loader: String,
+}
+
+/// Looks for .minefetch.toml in the current directory and its parents
+pub async fn find_marker() -> Result<Option<(PathBuf, Marker)>, MineFetchError> {
+    let current_dir = std::env::current_dir()?;
+
+    for dir in current_dir.ancestors() {
+        let path = dir.join(MARKER_NAME);
+
+        let contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(MineFetchError::io(&path, error)),
+        };
+
+        let marker: Marker = toml::from_str(&contents)
+            .map_err(|error| MineFetchError::parse(path.display().to_string(), error))?;
+
+        return Ok(Some((path, marker)));
+    }
+
+    Ok(None)
+}
+
+impl InlineProfile {
+    /// Turns the inline profile into a Profile.
+    /// The hash comes from the marker's path, so it's the same every run
+    pub fn into_profile(self, marker_path: &Path) -> Profile {
+        let base = marker_path.parent().unwrap_or(Path::new("."));
+
+        let hash = format!(
+            "{:x}",
+            Sha1::digest(marker_path.display().to_string().as_bytes())
+        );
+
+        Profile {
+            active: false,
+            name: self.name,
+            modsfolder: base.join(self.modsfolder).display().to_string(),
+            gameversion: self.gameversion,
+            loader: self.loader,
+            hash,
+            instance: None,
+            loader_version: None,
+        }
+    }
+}
@@ -0,0 +1,225 @@
+/*
+ __  __      _            _       _           ____           _
+|  \/  | ___| |_ __ _  __| | __ _| |_ __ _   / ___|__ _  ___| |__   ___
+| |\/| |/ _ \ __/ _` |/ _` |/ _` | __/ _` | | |   / _` |/ __| '_ \ / _ \
+| |  | |  __/ || (_| | (_| | (_| | || (_| | | |__| (_| | (__| | | |  __/
+|_|  |_|\___|\__\__,_|\__,_|\__,_|\__\__,_|  \____\__,_|\___|_| |_|\___|
+
+*/
+
+// Standard imports
+use std::path::{Path, PathBuf};
+use std::result::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// External crates
+use reqwest::StatusCode;
+use reqwest::Url;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+// Internal modules
+use crate::consts::{DEFAULT_CACHE_TTL_SECS, METACACHE_MAX_AGE_SECS};
+use crate::error::MineFetchError;
+use crate::http::send;
+use crate::structs::WorkingProfile;
+use crate::utils::{atomic_write, get_cachedir, get_homedir, is_verbose};
+
+/// One cached response
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    etag: Option<String>,
+    fetched_at: u64,
+    body: String,
+}
+
+/// Sends a GET request for Modrinth metadata, using the on-disk cache.
+/// Fresh entries are used as they are, old ones are revalidated with ETag
+pub async fn get_cached_json<T: DeserializeOwned>(
+    working_profile: &WorkingProfile,
+    url: Url,
+) -> Result<T, MineFetchError> {
+    let path = entry_path(&url).await?;
+    let ttl = working_profile
+        .settings
+        .cache_ttl
+        .unwrap_or(DEFAULT_CACHE_TTL_SECS);
+
+    // --refresh ignores the cache completely
+    let cached = match working_profile.options.refresh {
+        true => None,
+        false => read_entry(&path).await,
+    };
+
+    if let Some(entry) = &cached {
+        // Offline: whatever is cached is better than nothing
+        if working_profile.options.offline || now() < entry.fetched_at.saturating_add(ttl) {
+            if is_verbose() {
+                eprintln!(":dbg: Cached {url}");
+            }
+
+            return parse(&url, &entry.body);
+        }
+    }
+
+    if working_profile.options.offline {
+        return Err(MineFetchError::NotFound(format!(
+            "{url} isn't cached and can't be fetched offline"
+        )));
+    }
+
+    // Ask the server if the cached copy is still valid
+    let mut request = working_profile.client.get(url.clone());
+    if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_ref()) {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+
+    let response = send(request).await?;
+
+    let entry = match (response.status(), cached) {
+        // Nothing changed, just renew the entry
+        (StatusCode::NOT_MODIFIED, Some(entry)) => Entry {
+            fetched_at: now(),
+            ..entry
+        },
+        _ => Entry {
+            url: url.to_string(),
+            etag: response
+                .headers()
+                .get(ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map(String::from),
+            fetched_at: now(),
+            body: response.text().await?,
+        },
+    };
+
+    let parsed = parse(&url, &entry.body)?;
+
+    // A broken cache shouldn't break the command
+    if let Err(error) = write_entry(&path, &entry).await {
+        eprintln!(":wrn: Couldn't write the metadata cache: {error}");
+    }
+
+    prune().await;
+
+    Ok(parsed)
+}
+
+/// Deletes entries that weren't fetched for a long time (once per run).
+/// They are still useful offline, so only really old ones go away
+async fn prune() {
+    static PRUNED: AtomicBool = AtomicBool::new(false);
+
+    if PRUNED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let Ok(dir) = get_cachedir().await.map(|dir| dir.join("http")) else {
+        return;
+    };
+
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return;
+    };
+
+    let max_age = Duration::from_secs(METACACHE_MAX_AGE_SECS);
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let old = entry
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+
+        if old {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+}
+
+/// Parses the cached body
+fn parse<T: DeserializeOwned>(url: &Url, body: &str) -> Result<T, MineFetchError> {
+    serde_json::from_str(body)
+        .map_err(|error| MineFetchError::parse(format!("the response from {url}"), error))
+}
+
+/// Every URL has its own file named after the URL's hash
+async fn entry_path(url: &Url) -> Result<PathBuf, MineFetchError> {
+    let hash = format!("{:x}", Sha1::digest(url.as_str().as_bytes()));
+
+    Ok(get_cachedir()
+        .await?
+        .join("http")
+        .join(format!("{hash}.json")))
+}
+
+/// Reads a cached entry (None if there's no such entry or it's broken)
+async fn read_entry(path: &PathBuf) -> Option<Entry> {
+    let text = tokio::fs::read_to_string(path).await.ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Writes a cached entry
+async fn write_entry(path: &PathBuf, entry: &Entry) -> Result<(), MineFetchError> {
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|error| MineFetchError::io(parent, error))?;
+
+        // The new cache replaces the old one
+        remove_legacy_cache(parent).await;
+    }
+
+    atomic_write(path, serde_json::to_string(entry)?).await
+}
+
+/// Removes the cache from before $XDG_CACHE_HOME was used (~/.config/minefetch/cache/http).
+/// Anything else in that folder isn't ours and stays
+async fn remove_legacy_cache(dir: &Path) {
+    let Ok(legacy) = get_homedir()
+        .await
+        .map(|home| home.join(".config").join("minefetch").join("cache"))
+    else {
+        return;
+    };
+
+    let http = legacy.join("http");
+    if http == dir || !http.is_dir() {
+        return;
+    }
+
+    if let Err(error) = tokio::fs::remove_dir_all(&http).await {
+        eprintln!(
+            ":wrn: Couldn't remove the old metadata cache: {}",
+            MineFetchError::io(&http, error)
+        );
+        return;
+    }
+
+    // Only if nothing else is left there
+    let _ = tokio::fs::remove_dir(&legacy).await;
+
+    if is_verbose() {
+        eprintln!(
+            ":dbg: Removed the old metadata cache in {}",
+            legacy.display()
+        );
+    }
+}
+
+/// Current UNIX time in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
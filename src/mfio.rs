@@ -7,14 +7,19 @@
 
 */
 
+// Standard imports
+use std::io::IsTerminal;
 use std::num::ParseIntError;
 
 // External crates
 use console::{Key, Term};
-use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader, stdin, stdout};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, stdin, stdout};
+
+// Internal modules
+use crate::error::MineFetchError;
 
 /// Reads user input and returns a String
-pub async fn ainput(prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
+pub async fn ainput(prompt: &str) -> Result<String, MineFetchError> {
     // Print the prompt
     let mut stdout = stdout();
     stdout.write_all(prompt.as_bytes()).await?;
@@ -34,7 +39,7 @@ pub async fn ainput(prompt: &str) -> Result<String, Box<dyn std::error::Error>>
 }
 
 /// Parses String to Vec<usize>
-pub fn parse_to_int(string: String) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+pub fn parse_to_int(string: String) -> Result<Vec<usize>, MineFetchError> {
     // Split the string
     let splitted_string = string.split(' ');
 
@@ -78,19 +83,24 @@ impl std::fmt::Display for MFText {
 }
 
 /// A replacement for inquire. Works better
-pub async fn select<S, T>(prompt: &str, options: Vec<(S, T)>) -> io::Result<T>
+pub async fn select<S, T>(prompt: &str, options: Vec<(S, T)>) -> Result<T, MineFetchError>
 where
     S: AsRef<str>,
     T: Clone, // Makes function take any type of value which implements 'Clone' as an input
 {
     if options.is_empty() {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "No options provided",
-        ));
+        return Err(MineFetchError::NotFound("No options provided".into()));
     }
 
     let term = Term::stdout();
+
+    // Without a terminal there are no keys to read
+    if !term.is_term() || !std::io::stdin().is_terminal() {
+        return Err(MineFetchError::Invalid(format!(
+            "'{prompt}' needs an interactive terminal"
+        )));
+    }
+
     let mut index = 0;
     let total = options.len();
 
@@ -104,9 +114,7 @@ where
         term.clear_screen()?;
         println!(":out: {prompt}");
 
-        let start = if total <= viewport_size {
-            0
-        } else if index < viewport_size / 2 {
+        let start = if total <= viewport_size || index < viewport_size / 2 {
             0
         } else if index > total - (viewport_size / 2 + 1) {
             total - viewport_size
@@ -116,7 +124,7 @@ where
 
         let end = (start + viewport_size).min(total);
         for (i, (label, _)) in options.iter().enumerate().skip(start).take(end - start) {
-            let max_width = columns as usize - 3;
+            let max_width = columns - 3;
 
             let raw_string = label.as_ref();
 
@@ -140,10 +148,7 @@ where
             Key::Escape | Key::Char('q') => {
                 term.clear_last_lines(end - start)?;
                 Term::show_cursor(&term)?;
-                return Err(io::Error::new(
-                    io::ErrorKind::Interrupted,
-                    "Selection cancelled",
-                ));
+                return Err(MineFetchError::Cancelled);
             }
             _ => {}
         }
@@ -0,0 +1,196 @@
+/*
+ __  __ _                 _
+|  \/  (_) __ _ _ __ __ _| |_ ___
+| |\/| | |/ _` | '__/ _` | __/ _ \
+| |  | | | (_| | | | (_| | ||  __/
+|_|  |_|_|\__, |_|  \__,_|\__\___|
+          |___/
+
+*/
+
+// Standard imports
+use std::path::{Path, PathBuf};
+
+// External crates
+use serde::de::DeserializeOwned;
+use toml::{Table, Value};
+
+// Internal modules
+use crate::error::MineFetchError;
+use crate::filelock::try_lock_config;
+use crate::utils::atomic_write;
+
+// Current schema versions. Files without 'schema_version' are version 0
+// (minefetch.lock has always had it)
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+pub const CACHE_SCHEMA_VERSION: u32 = 1;
+pub const LOCKS_SCHEMA_VERSION: u32 = 1;
+pub const LOCKFILE_SCHEMA_VERSION: u32 = 1;
+
+/// Upgrades a file from one version to the next one
+type Migration = fn(&mut Table) -> Result<(), MineFetchError>;
+
+/// Files that have a schema version
+#[derive(Clone, Copy)]
+pub enum Schema {
+    Config,
+    Cache,
+    Locks,
+    Lockfile,
+}
+
+impl Schema {
+    /// The version this MineFetch writes
+    fn current(self) -> u32 {
+        match self {
+            Schema::Config => CONFIG_SCHEMA_VERSION,
+            Schema::Cache => CACHE_SCHEMA_VERSION,
+            Schema::Locks => LOCKS_SCHEMA_VERSION,
+            Schema::Lockfile => LOCKFILE_SCHEMA_VERSION,
+        }
+    }
+
+    /// The oldest version that can be read
+    fn oldest(self) -> u32 {
+        match self {
+            Schema::Lockfile => 1,
+            _ => 0,
+        }
+    }
+
+    /// Migrations, the one at index N upgrades version oldest + N to the next one
+    fn migrations(self) -> &'static [Migration] {
+        match self {
+            Schema::Config => &[unversioned],
+            Schema::Cache => &[unversioned],
+            Schema::Locks => &[unversioned],
+            Schema::Lockfile => &[],
+        }
+    }
+
+    /// Whether an upgraded file is saved. minefetch.lock is kept in git
+    /// next to the user's files, so it's only upgraded in memory
+    fn saves_upgraded(self) -> bool {
+        !matches!(self, Schema::Lockfile)
+    }
+}
+
+/// 0 -> 1: the format is the same, only 'schema_version' is added
+fn unversioned(_table: &mut Table) -> Result<(), MineFetchError> {
+    Ok(())
+}
+
+/// Parses a TOML file, upgrading it in place (with a backup) if it's older than this MineFetch
+pub async fn load<T: DeserializeOwned>(
+    path: &Path,
+    contents: &str,
+    schema: Schema,
+) -> Result<T, MineFetchError> {
+    let parse_error =
+        |error: toml::de::Error| MineFetchError::parse(path.display().to_string(), error);
+
+    let mut table: Table = toml::from_str(contents).map_err(parse_error)?;
+
+    let version = match table.get("schema_version") {
+        None => 0,
+        Some(Value::Integer(version)) if *version >= 0 => {
+            u32::try_from(*version).map_err(|_| {
+                MineFetchError::Config(format!(
+                    "{} has an impossible schema version {version}",
+                    path.display()
+                ))
+            })?
+        }
+        Some(_) => {
+            return Err(MineFetchError::parse(
+                path.display().to_string(),
+                "schema_version must be a positive number",
+            ));
+        }
+    };
+
+    if version < schema.oldest() {
+        return Err(MineFetchError::parse(
+            path.display().to_string(),
+            "schema_version is missing or older than any MineFetch has written",
+        ));
+    }
+
+    let current = schema.current();
+
+    // Don't guess what a newer MineFetch meant
+    if version > current {
+        return Err(MineFetchError::Config(format!(
+            "{} was written by a newer MineFetch (schema version {version}, this one supports {current}), please upgrade MineFetch",
+            path.display()
+        )));
+    }
+
+    if version < current {
+        for migration in &schema.migrations()[(version - schema.oldest()) as usize..] {
+            migration(&mut table)?;
+        }
+
+        table.insert("schema_version".into(), Value::Integer(current.into()));
+
+        if schema.saves_upgraded() {
+            save_upgraded(path, contents, &table, version, schema).await;
+        }
+    }
+
+    table.try_into().map_err(parse_error)
+}
+
+/// Saves the upgraded file after a backup of the old one.
+/// Nothing here is fatal: a file that can't be saved is migrated in memory again next time
+async fn save_upgraded(path: &Path, contents: &str, table: &Table, version: u32, schema: Schema) {
+    // Profile commands change config.toml under this lock.
+    // It's not waited for: this process may be holding it already
+    let _lock = match schema {
+        Schema::Config => match try_lock_config().await {
+            Some(lock) => Some(lock),
+            None => return,
+        },
+        _ => None,
+    };
+
+    // Someone has changed the file since it was read, don't overwrite it
+    if tokio::fs::read_to_string(path).await.ok().as_deref() != Some(contents) {
+        return;
+    }
+
+    let upgraded = match toml::to_string(table) {
+        Ok(upgraded) => upgraded,
+        Err(error) => {
+            eprintln!(":wrn: Couldn't save the upgraded file: {error}");
+            return;
+        }
+    };
+
+    // Keep the old file, just in case (a read-only folder can't have it, so the file stays as it is)
+    let backup = backup_path(path, version);
+    if let Err(error) = tokio::fs::copy(path, &backup).await {
+        eprintln!(
+            ":wrn: Couldn't save the upgraded file: {}",
+            MineFetchError::io(&backup, error)
+        );
+        return;
+    }
+
+    match atomic_write(path, upgraded).await {
+        Ok(()) => eprintln!(
+            ":out: Upgraded {} to schema version {} (backup: {})",
+            path.display(),
+            schema.current(),
+            backup.display()
+        ),
+        Err(error) => eprintln!(":wrn: Couldn't save the upgraded file: {error}"),
+    }
+}
+
+/// config.toml -> config.toml.v0.bak
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{version}.bak"));
+    PathBuf::from(backup)
+}
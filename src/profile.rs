@@ -11,88 +11,101 @@
 use std::path::{Path, PathBuf};
 use std::result::Result;
 
-// External crates
-use reqwest::Client;
-
 // Internal imports
 use crate::cache::list_mods_cached;
+use crate::error::MineFetchError;
+use crate::http::build_client;
+use crate::instance::check_instance;
+use crate::marker::{MarkerProfile, find_marker};
 use crate::mfio::select;
-use crate::structs::{Config, Locks, MFHashMap, Profile, WorkingProfile};
-use crate::utils::get_confpath;
+use crate::migrate::{LOCKS_SCHEMA_VERSION, Schema, load};
+use crate::structs::{Config, Locks, Options, Profile, WorkingProfile};
+use crate::utils::{atomic_write, get_confdir, get_confpath};
 
-/// Returns single active Profile
-pub async fn read_config() -> Result<Profile, Box<dyn std::error::Error>> {
+/// Returns full Config
+pub async fn read_full_config() -> Result<Config, MineFetchError> {
     // Get config path
     let config_path = get_confpath().await?;
 
     // Read config
-    let contents = match tokio::fs::read_to_string(&config_path).await {
-        Ok(contents) => contents,
-        Err(_) => return Err("There's no config yet, type minefetch profile create".into()),
-    };
+    let contents = tokio::fs::read_to_string(&config_path)
+        .await
+        .map_err(|_| no_config())?;
 
-    // Parse config
-    let config: Config = toml::from_str(&contents)?;
+    // Parse config (older versions are upgraded)
+    let config: Config = load(&config_path, &contents, Schema::Config).await?;
 
-    // Return active profile
-    config
-        .profile
-        .into_iter()
-        .find(|profile| profile.active) // Searching for only active one
-        .ok_or_else(|| "No active profile found".into())
+    // Return full config, including inactive profiles
+    Ok(config)
 }
 
-/// Returns full Config
-pub async fn read_full_config() -> Result<Config, Box<dyn std::error::Error>> {
-    // Get config path
+/// Writes the config, creating its directory if needed
+pub async fn write_config(config: &Config) -> Result<(), MineFetchError> {
+    // Translate into toml string
+    let string_toml = toml::to_string(config)?;
+
+    // Get a config path
     let config_path = get_confpath().await?;
 
-    // Read config
-    let contents = tokio::fs::read_to_string(&config_path).await?;
+    // Create a config folder if it doesn't exist
+    let config_dir = get_confdir().await?;
+    tokio::fs::create_dir_all(&config_dir)
+        .await
+        .map_err(|error| MineFetchError::io(&config_dir, error))?;
 
-    // Parse config
-    let config: Config = toml::from_str(&contents)?;
+    // Write a config
+    atomic_write(&config_path, string_toml).await?;
 
-    // Return full config, including inactive profiles
-    Ok(config)
+    Ok(())
+}
+
+/// The error which is returned when there's no config file
+pub fn no_config() -> MineFetchError {
+    MineFetchError::Config("There's no config yet, type minefetch profile create".into())
 }
 
 /// Gets a list of locks
-pub async fn get_locks(profile: &Profile) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+pub async fn get_locks(profile: &Profile) -> Result<Vec<String>, MineFetchError> {
     // Get a lock path
     let locks_path = get_locks_path(profile);
 
     // Read a lock list
-    let string = match tokio::fs::read_to_string(locks_path).await {
+    let string = match tokio::fs::read_to_string(&locks_path).await {
         Ok(string) => string,
-        Err(_) => {
-            return Err(format!("The profile {} doesn't have any locks yet", profile.name).into());
-        }
+        Err(_) => return Err(no_locks(profile)),
     };
 
-    // Parse the toml string
-    let locks: Locks = toml::from_str(&string)?;
+    // Parse the toml string (older versions are upgraded)
+    let locks: Locks = load(&locks_path, &string, Schema::Locks).await?;
 
     // If empty then return an error
     if locks.lock.is_empty() {
-        return Err(format!("The profile {} doesn't have any locks yet", profile.name).into());
+        return Err(no_locks(profile));
     }
 
     // Return locks
     Ok(locks.lock)
 }
 
+/// The error which is returned when the profile has no locks
+fn no_locks(profile: &Profile) -> MineFetchError {
+    MineFetchError::NotFound(format!(
+        "The profile {} doesn't have any locks yet",
+        profile.name
+    ))
+}
+
 /// Adds a lock
-pub async fn add_lock(working_profile: &WorkingProfile) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn add_lock(working_profile: &WorkingProfile) -> Result<(), MineFetchError> {
     // Get a mod list
-    let versions = list_mods_cached(&working_profile).await?;
+    let versions = list_mods_cached(working_profile).await?;
 
     // Create a mutable mod menu
     let mut modmenu: Vec<(String, String)> = Vec::new();
 
     // Push the version files into 'modmenu'
     for version in versions {
-        modmenu.push((version.title.unwrap(), version.hash))
+        modmenu.push((version.title.unwrap_or(version.filename), version.hash))
     }
 
     // Select a hash
@@ -106,33 +119,31 @@ pub async fn add_lock(working_profile: &WorkingProfile) -> Result<(), Box<dyn st
 }
 
 /// Writes a new lock into the file
-pub async fn write_lock(profile: &Profile, hash: String) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn write_lock(profile: &Profile, hash: String) -> Result<(), MineFetchError> {
     // Get a mutable lock list
-    let mut locks = match get_locks(&profile).await {
-        Ok(locks) => locks,
-        Err(_) => Vec::new(),
-    };
+    let mut locks = get_locks(profile).await.unwrap_or_default();
 
     // Push a new hash into the lock list
     locks.push(hash);
 
     // Create a new lock list structure
-    let new_locks = Locks { lock: locks };
+    let new_locks = Locks {
+        schema_version: LOCKS_SCHEMA_VERSION,
+        lock: locks,
+    };
 
     // Get a locks' path
-    let locks_path = get_locks_path(&profile);
+    let locks_path = get_locks_path(profile);
 
     // Write into the file
-    tokio::fs::write(locks_path, toml::to_string(&new_locks)?).await?;
+    atomic_write(&locks_path, toml::to_string(&new_locks)?).await?;
 
     // Success
     Ok(())
 }
 
 /// Removes a lock
-pub async fn remove_lock(
-    working_profile: &WorkingProfile,
-) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn remove_lock(working_profile: &WorkingProfile) -> Result<(), MineFetchError> {
     // Get a mutable lock list
     let mut locks = get_locks(&working_profile.profile).await?;
 
@@ -140,7 +151,7 @@ pub async fn remove_lock(
     let mut lockmenu: Vec<(String, String)> = Vec::new();
 
     // Get a mod list
-    let mods = list_mods_cached(&working_profile).await?;
+    let mods = list_mods_cached(working_profile).await?;
 
     /*
         Go through all locks and get an
@@ -148,10 +159,7 @@ pub async fn remove_lock(
     */
     for lock in &locks {
         // Get a each version info using its hash
-        let (name, filename) = match mods
-            .iter()
-            .find_map(|v| if v.hash == *lock { Some(v) } else { None })
-        {
+        let (name, filename) = match mods.iter().find(|v| v.hash == *lock) {
             // If it's in the mod list then clone its info
             Some(value) => (value.title.clone(), value.filename.clone()),
 
@@ -165,7 +173,7 @@ pub async fn remove_lock(
 
         // Push the info into lock menu
         lockmenu.push((
-            format!("{} ({})", name.unwrap(), filename),
+            format!("{} ({})", name.unwrap_or_default(), filename),
             lock.to_string(),
         ));
     }
@@ -180,56 +188,39 @@ pub async fn remove_lock(
     locks.retain(|lock| lock != &hash);
 
     // Create a new lock structure
-    let locks = Locks { lock: locks };
+    let locks = Locks {
+        schema_version: LOCKS_SCHEMA_VERSION,
+        lock: locks,
+    };
 
     // Translate into toml string
-    let locks_to_str = match toml::to_string(&locks) {
-        Ok(locks) => locks,
-        Err(error) => return Err(error.into()),
-    };
+    let locks_to_str = toml::to_string(&locks)?;
 
     // Get a locks' path
     let lockspath = get_locks_path(&working_profile.profile);
 
     // Write into the file
-    tokio::fs::write(lockspath, locks_to_str).await?;
+    atomic_write(&lockspath, locks_to_str).await?;
 
     // Success
     Ok(())
 }
 
-/// Removes locks (hashes) from the hashmap
-pub async fn remove_locked_ones(
-    hashmap: &mut MFHashMap,
-    locks: Vec<String>,
-) -> Result<&mut MFHashMap, Box<dyn std::error::Error>> {
-    /*
-        A loop which removes
-        the locks from the hashmap
-    */
-    for lock in locks {
-        hashmap.remove_entry(&lock);
-    }
-
-    // Return the modified hashmap
-    Ok(hashmap)
-}
-
 /// Gets the locks' path
 pub fn get_locks_path(profile: &Profile) -> PathBuf {
     // Join the mods' folder path with the locks' filename
-    return Path::join(Path::new(&profile.modsfolder), "locks.toml");
+    Path::join(Path::new(&profile.modsfolder), "locks.toml")
 }
 
 /// Lists all locks
 pub async fn list_locks(
     working_profile: &WorkingProfile,
-) -> Result<Vec<(usize, String, String)>, Box<dyn std::error::Error>> {
+) -> Result<Vec<(usize, String, String)>, MineFetchError> {
     // Get a locks' list
     let locks = get_locks(&working_profile.profile).await?;
 
     // Get a mods' list
-    let mods = list_mods_cached(&working_profile).await?;
+    let mods = list_mods_cached(working_profile).await?;
 
     // Set the counter
     let mut counter: usize = 1;
@@ -243,10 +234,7 @@ pub async fn list_locks(
     */
     for lock in locks {
         // Get a version by hash
-        let (name, filename) = match mods
-            .iter()
-            .find_map(|v| if v.hash == *lock { Some(v) } else { None })
-        {
+        let (name, filename) = match mods.iter().find(|v| v.hash == *lock) {
             // If it's in the mod list then clone its info
             Some(value) => (value.title.clone(), value.filename.clone()),
 
@@ -259,7 +247,7 @@ pub async fn list_locks(
         };
 
         // Push into the result
-        result.push((counter, name.unwrap(), filename));
+        result.push((counter, name.unwrap_or_default(), filename));
 
         // Append to the counter
         counter += 1;
@@ -270,16 +258,91 @@ pub async fn list_locks(
 }
 
 /// Creates a WorkingProfile which contains a Client and a Profile
-pub async fn build_working_profile() -> Result<WorkingProfile, Box<dyn std::error::Error>> {
-    // Read the profile
-    let profile = read_config().await?;
+pub async fn build_working_profile(options: &Options) -> Result<WorkingProfile, MineFetchError> {
+    // Read the config (a marker with an inline profile works without it)
+    let config = match read_full_config().await {
+        Ok(config) => config,
+        Err(_) if options.profile.is_none() && !get_confpath().await?.exists() => Config::default(),
+        Err(error) => return Err(error),
+    };
 
-    // Create a client
-    let client = Client::new();
+    let profile = select_profile(&config.profile, options).await?;
+
+    // The launcher could have changed the instance behind our back
+    check_instance(&profile).await;
+
+    // Create a client with timeouts
+    let client = build_client()?;
 
     // Create a WorkingProfile structure
-    let working_profile = WorkingProfile { profile, client };
+    let working_profile = WorkingProfile {
+        profile,
+        client,
+        settings: config.settings,
+        options: options.clone(),
+    };
 
     // Return the WorkingProfile
     Ok(working_profile)
 }
+
+/// Picks the profile to work with: --profile, then .minefetch.toml, then the active one
+pub async fn select_profile(
+    profiles: &[Profile],
+    options: &Options,
+) -> Result<Profile, MineFetchError> {
+    // Finds a profile by name or hash
+    let find = |name: &str| {
+        profiles
+            .iter()
+            .find(|profile| profile.name == name || profile.hash == name)
+            .cloned()
+    };
+
+    let marker = find_marker().await?;
+
+    // --profile selects a profile by name or hash without switching to it
+    if let Some(name) = &options.profile {
+        if let Some((path, _)) = &marker {
+            eprintln!(":out: --profile overrides {}", path.display());
+        }
+
+        return find(name)
+            .ok_or_else(|| MineFetchError::NotFound(format!("There's no profile named {name}")));
+    }
+
+    // .minefetch.toml in this directory or above
+    if let Some((path, marker)) = marker {
+        let profile = match marker.profile {
+            MarkerProfile::Name(name) => find(&name).ok_or_else(|| {
+                MineFetchError::NotFound(format!(
+                    "There's no profile named {name} (from {})",
+                    path.display()
+                ))
+            })?,
+            MarkerProfile::Inline(inline) => inline.into_profile(&path),
+        };
+
+        eprintln!(
+            ":out: Using profile {} [{} {}] from {}",
+            profile.name,
+            profile.loader,
+            profile.gameversion,
+            path.display()
+        );
+
+        return Ok(profile);
+    }
+
+    // Nothing to choose from
+    if profiles.is_empty() {
+        return Err(no_config());
+    }
+
+    // Get the active profile
+    profiles
+        .iter()
+        .find(|profile| profile.active) // Searching for only active one
+        .cloned()
+        .ok_or_else(|| MineFetchError::Config("No active profile found".into()))
+}
@@ -12,7 +12,10 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 // Standard imports
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+// Internal modules
+use crate::migrate::CONFIG_SCHEMA_VERSION;
 
 /// The structure that contains the hashes
 /// (The program uses only sha1 so there's only one value)
@@ -22,17 +25,18 @@ pub struct Hashes {
 }
 
 /// File info
-/// (hashes, url, filename, primary or not)
+/// (hashes, url, filename, primary or not, size in bytes)
 #[derive(Deserialize, Clone)]
 pub struct File {
     pub hashes: Hashes,
     pub url: String,
     pub filename: String,
     pub primary: bool,
+    pub size: u64,
 }
 
 /// Version info
-/// (name, files, dependencies, project id, version id)
+/// (name, files, dependencies, project id, version id, channel, date, changelog)
 #[derive(Deserialize, Clone)]
 pub struct Version {
     pub name: String,
@@ -40,6 +44,13 @@ pub struct Version {
     pub dependencies: Option<Vec<Dependency>>,
     pub project_id: String,
     pub id: String,
+
+    /// The version as the author wrote it (e.g. "0.6.3+mc1.21.1")
+    #[serde(default)]
+    pub version_number: String,
+    pub version_type: String,
+    pub date_published: String,
+    pub changelog: Option<String>,
 }
 
 /// Version List type
@@ -51,6 +62,12 @@ pub type MFHashMap = HashMap<String, Version>;
 /// Config structure
 #[derive(Deserialize, Serialize)]
 pub struct Config {
+    /// Format version of the file (see migrate.rs)
+    #[serde(default)]
+    pub schema_version: u32,
+
+    #[serde(default, skip_serializing_if = "Settings::is_empty")]
+    pub settings: Settings,
     pub profile: Vec<Profile>,
 }
 
@@ -59,11 +76,36 @@ pub struct Config {
 impl Default for Config {
     fn default() -> Self {
         Config {
+            schema_version: CONFIG_SCHEMA_VERSION,
+            settings: Settings::default(),
             profile: Vec::new(),
         }
     }
 }
 
+/// Global settings ([settings] table in config.toml)
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Settings {
+    /// How many files are downloaded at the same time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+
+    /// How long Modrinth metadata stays in the cache (seconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_ttl: Option<u64>,
+
+    /// Loader metadata services by loader name (e.g. a local stand-in for testing)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub loader_meta: BTreeMap<String, String>,
+}
+
+impl Settings {
+    /// True if nothing is set (the table isn't written then)
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_none() && self.cache_ttl.is_none() && self.loader_meta.is_empty()
+    }
+}
+
 /// Profile structure
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Profile {
@@ -73,6 +115,14 @@ pub struct Profile {
     pub gameversion: String,
     pub loader: String,
     pub hash: String,
+
+    /// Prism Launcher / MultiMC instance the profile was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+
+    /// Version of the server loader installed with 'minefetch loader install'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader_version: Option<String>,
 }
 
 /// Structure of the search response
@@ -86,6 +136,23 @@ pub struct Search {
 pub struct Hit {
     pub project_id: String,
     pub title: String,
+    pub description: String,
+    pub author: String,
+    pub downloads: u64,
+    pub date_modified: String,
+    pub client_side: String,
+    pub server_side: String,
+}
+
+/// Search parameters
+/// (query, page size, page number, sorting, extra facets)
+pub struct SearchQuery {
+    pub query: String,
+    pub limit: usize,
+    pub page: usize,
+    pub sort: String,
+    pub categories: Vec<String>,
+    pub author: Option<String>,
 }
 
 /// Project structure
@@ -97,6 +164,41 @@ pub struct Project {
 
 pub type ProjectList = Vec<Project>;
 
+/// Full project info from /project/{id}
+#[derive(Deserialize)]
+pub struct ProjectInfo {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub license: License,
+    pub source_url: Option<String>,
+    pub issues_url: Option<String>,
+    pub wiki_url: Option<String>,
+    pub loaders: Vec<String>,
+    pub game_versions: Vec<String>,
+}
+
+/// Project license
+#[derive(Deserialize)]
+pub struct License {
+    pub id: String,
+    pub name: String,
+}
+
+/// Team member of the project
+#[derive(Deserialize)]
+pub struct Member {
+    pub user: User,
+    pub role: String,
+}
+
+/// Modrinth user
+#[derive(Deserialize)]
+pub struct User {
+    pub username: String,
+}
+
 /// Hash structure
 /// (hashes, algorithm, loaders, game versions)
 #[derive(Serialize)]
@@ -117,13 +219,97 @@ pub struct Dependency {
 /// Locks structure
 #[derive(Deserialize, Serialize)]
 pub struct Locks {
+    /// Format version of the file (see migrate.rs)
+    #[serde(default)]
+    pub schema_version: u32,
+
     pub lock: Vec<String>,
 }
 
+/// One row of 'minefetch outdated --json'
+#[derive(Serialize)]
+pub struct OutdatedMod {
+    pub title: String,
+    pub project_id: String,
+    pub installed_version: String,
+    pub available_version: String,
+    pub filename: String,
+    pub channel: String,
+    pub locked: bool,
+}
+
+/// Changes for 'minefetch profile edit' (None keeps the current value)
+#[derive(Default)]
+pub struct ProfileEdit {
+    pub name: Option<String>,
+    pub modsfolder: Option<String>,
+    pub gameversion: Option<String>,
+    pub loader: Option<String>,
+
+    /// Re-resolve installed mods without asking
+    pub resolve: bool,
+}
+
+impl ProfileEdit {
+    /// True if no field was given
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.modsfolder.is_none()
+            && self.gameversion.is_none()
+            && self.loader.is_none()
+    }
+}
+
+/// Arguments of 'minefetch profile clone'
+pub struct ProfileClone {
+    /// Name or hash of the profile to clone
+    pub source: String,
+    pub modsfolder: String,
+    pub name: Option<String>,
+    pub gameversion: Option<String>,
+}
+
+/// Arguments of 'minefetch sync'
+#[derive(Default)]
+pub struct SyncArgs {
+    /// Path to minefetch.toml (searched in the current directory and its parents if not set)
+    pub manifest: Option<String>,
+
+    /// Only install mods for this side (client or server)
+    pub side: Option<String>,
+
+    /// Only show what would change
+    pub dry_run: bool,
+}
+
+/// Global command-line options
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Don't touch the network, use cache.toml and the jars themselves
+    pub offline: bool,
+
+    /// Don't use the cached Modrinth metadata
+    pub refresh: bool,
+
+    /// Print machine-readable JSON instead of text
+    pub json: bool,
+
+    /// Use this profile (name or hash) instead of the active one
+    pub profile: Option<String>,
+
+    /// Run the command for every profile
+    pub all_profiles: bool,
+
+    /// Answer yes to every confirmation
+    pub yes: bool,
+}
+
 /// Working profile structure
-/// (Profile and Client)
+/// (Profile, Client, global settings and command-line options)
 #[derive(Clone)]
 pub struct WorkingProfile {
     pub profile: Profile,
     pub client: Client,
+    pub settings: Settings,
+    pub options: Options,
 }
@@ -15,15 +15,21 @@ use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
 use std::result::Result;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Once, OnceLock};
 
 // External crates
 use rand::Rng;
 use rand::distr::Alphanumeric;
 use sha1::{Digest, Sha1};
+use tokio::io::AsyncWriteExt;
 use tokio::task::spawn_blocking;
 
+// Internal modules
+use crate::error::MineFetchError;
+
 /// Generates random 64 char string
-pub async fn generate_hash() -> Result<String, Box<dyn std::error::Error>> {
+pub async fn generate_hash() -> Result<String, MineFetchError> {
     // Get a random hash
     let random_hash = tokio::task::spawn_blocking(|| {
         rand::rng()
@@ -39,10 +45,19 @@ pub async fn generate_hash() -> Result<String, Box<dyn std::error::Error>> {
 }
 
 /// Returns Vec<String> of hashes in given path
-pub async fn get_hashes(path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+pub async fn get_hashes(path: &str) -> Result<Vec<String>, MineFetchError> {
+    Ok(get_hashed_jars(path)
+        .await?
+        .into_iter()
+        .map(|(_, hash)| hash)
+        .collect())
+}
+
+/// Returns paths of the jars in given path together with their hashes
+pub async fn get_hashed_jars(path: &str) -> Result<Vec<(PathBuf, String)>, MineFetchError> {
     let mut dir = tokio::fs::read_dir(path)
         .await
-        .map_err(|_| "There's no mods yet")?;
+        .map_err(|_| MineFetchError::NotFound("There's no mods yet".into()))?;
 
     let mut paths: Vec<PathBuf> = Vec::new();
 
@@ -56,7 +71,9 @@ pub async fn get_hashes(path: &str) -> Result<Vec<String>, Box<dyn std::error::E
 
     for p in paths {
         if p.extension().unwrap_or_default() == "jar" {
-            handles.push(spawn_blocking(move || calculate_sha1(&p)));
+            handles.push(spawn_blocking(move || {
+                calculate_sha1(&p).map(|hash| (p, hash))
+            }));
         }
     }
 
@@ -76,14 +93,49 @@ pub async fn get_hashes(path: &str) -> Result<Vec<String>, Box<dyn std::error::E
     }
 
     if hashes.is_empty() {
-        return Err("No valid entries found to calculate hashes".into());
+        return Err(MineFetchError::NotFound(
+            "No valid entries found to calculate hashes".into(),
+        ));
     }
 
     Ok(hashes)
 }
 
+/// Writes a file atomically: into a temporary file first, then renames it over the old one.
+/// A crash can leave the old file or the new one, but never a half-written one
+pub async fn atomic_write(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<(), MineFetchError> {
+    let path = path.as_ref();
+
+    // Tasks of one process can write at the same time too
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let number = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    // config.toml -> config.toml.tmp<pid>-<n>, in the same directory so rename is atomic
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".tmp{}-{}", std::process::id(), number));
+    let temp = PathBuf::from(temp);
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temp, path).await
+    }
+    .await;
+
+    if let Err(error) = result {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(MineFetchError::io(path, error));
+    }
+
+    Ok(())
+}
+
 /// Synchronous SHA-1 calculation using a buffered reader (used inside spawn_blocking)
-fn calculate_sha1(path: &Path) -> std::io::Result<String> {
+pub fn calculate_sha1(path: &Path) -> std::io::Result<String> {
     let f = File::open(path)?;
     let mut reader = BufReader::with_capacity(64 * 1024, f); // 64 KiB buffer
     let mut hasher = Sha1::new();
@@ -104,10 +156,12 @@ fn calculate_sha1(path: &Path) -> std::io::Result<String> {
 /// Deletes files in folder with same hash
 pub async fn remove_mods_by_hash(
     modsfolder: &str,
-    hashes_to_remove: &Vec<&String>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    hashes_to_remove: &[&String],
+) -> Result<(), MineFetchError> {
     // Read mods' folder
-    let mut entries = tokio::fs::read_dir(modsfolder).await?;
+    let mut entries = tokio::fs::read_dir(modsfolder)
+        .await
+        .map_err(|error| MineFetchError::io(modsfolder, error))?;
 
     // Go through every file in the folder
     while let Some(entry) = entries.next_entry().await? {
@@ -117,11 +171,14 @@ pub async fn remove_mods_by_hash(
         // If it's a file
         if path.is_file() {
             // Get a hash
-            let file_hash = calculate_sha1(&path)?;
+            let file_hash =
+                calculate_sha1(&path).map_err(|error| MineFetchError::io(&path, error))?;
 
             // If the hash in the hash list then remove a file
             if hashes_to_remove.contains(&&file_hash) {
-                tokio::fs::remove_file(&path).await?;
+                tokio::fs::remove_file(&path)
+                    .await
+                    .map_err(|error| MineFetchError::io(&path, error))?;
             }
         }
     }
@@ -131,35 +188,116 @@ pub async fn remove_mods_by_hash(
 }
 
 /// Gets a home folder (Not sure if it works for windows)
-pub async fn get_homedir() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let homedir = env::home_dir().ok_or("Can't get the home directory")?;
+pub async fn get_homedir() -> Result<PathBuf, MineFetchError> {
+    let homedir = env::home_dir()
+        .ok_or_else(|| MineFetchError::Config("Can't get the home directory".into()))?;
 
     Ok(homedir)
 }
 
-/// Gets a config path
-pub async fn get_confpath() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // Get a home folder
-    let homedir = get_homedir().await?;
+/// Looks for a file in the current directory and its parents
+pub fn find_in_ancestors(name: &str) -> Result<Option<PathBuf>, MineFetchError> {
+    let current_dir = env::current_dir()?;
+
+    Ok(current_dir
+        .ancestors()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file()))
+}
+
+/// Config file given with --config (set once at startup)
+static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Makes every command use this config file instead of the default one
+pub fn set_config_override(path: PathBuf) {
+    let _ = CONFIG_OVERRIDE.set(path);
+}
+
+/// Set once at startup by --verbose
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Makes every part of MineFetch print debug messages
+pub fn set_verbose() {
+    VERBOSE.store(true, Ordering::Relaxed);
+}
 
-    /*
-        Join the home folder with
-        the config location and return
-    */
-    Ok(homedir
-        .join(".config")
-        .join("minefetch")
-        .join("config.toml"))
+/// True if --verbose is set
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Returns an XDG directory from the environment if it's set to an absolute path
+fn xdg_dir(variable: &str) -> Option<PathBuf> {
+    env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+/// Returns the config file from --config or $MINEFETCH_CONFIG
+fn config_override() -> Option<PathBuf> {
+    CONFIG_OVERRIDE.get().cloned().or_else(|| {
+        env::var_os("MINEFETCH_CONFIG")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Gets a config path
+/// (--config, then $MINEFETCH_CONFIG, then config.toml in the config directory)
+pub async fn get_confpath() -> Result<PathBuf, MineFetchError> {
+    match config_override() {
+        Some(path) => Ok(path),
+        None => Ok(get_confdir().await?.join("config.toml")),
+    }
 }
 
 /// Gets a config directory
-pub async fn get_confdir() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // Get a home folder
-    let homedir = get_homedir().await?;
-
-    /*
-        Join the home folder with the
-        config folder location and return
-    */
-    Ok(homedir.join(".config").join("minefetch"))
+/// (the directory of the overridden config, $XDG_CONFIG_HOME/minefetch or ~/.config/minefetch)
+pub async fn get_confdir() -> Result<PathBuf, MineFetchError> {
+    // An overridden config lives in its own directory
+    if let Some(path) = config_override() {
+        return Ok(path.parent().map(Path::to_path_buf).unwrap_or_default());
+    }
+
+    let legacy = get_homedir().await?.join(".config").join("minefetch");
+
+    let Some(xdg) = xdg_dir("XDG_CONFIG_HOME").map(|dir| dir.join("minefetch")) else {
+        return Ok(legacy);
+    };
+
+    // Configs from before $XDG_CONFIG_HOME was read stay where they are
+    if !xdg.join("config.toml").exists() && legacy.join("config.toml").exists() {
+        static NOTICE: Once = Once::new();
+        NOTICE.call_once(|| {
+            eprintln!(
+                ":out: Using {} ({} doesn't have config.toml, move it there to use $XDG_CONFIG_HOME)",
+                legacy.display(),
+                xdg.display()
+            )
+        });
+
+        return Ok(legacy);
+    }
+
+    Ok(xdg)
+}
+
+/// Gets the base directory for user data
+/// ($XDG_DATA_HOME or ~/.local/share)
+pub async fn get_datahome() -> Result<PathBuf, MineFetchError> {
+    match xdg_dir("XDG_DATA_HOME") {
+        Some(dir) => Ok(dir),
+        None => Ok(get_homedir().await?.join(".local").join("share")),
+    }
+}
+
+/// Gets a directory for cached data
+/// ($XDG_CACHE_HOME/minefetch or ~/.cache/minefetch)
+pub async fn get_cachedir() -> Result<PathBuf, MineFetchError> {
+    let base = match xdg_dir("XDG_CACHE_HOME") {
+        Some(dir) => dir,
+        None => get_homedir().await?.join(".cache"),
+    };
+
+    Ok(base.join("minefetch"))
 }